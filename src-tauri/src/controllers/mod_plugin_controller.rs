@@ -1,24 +1,51 @@
+#[path = "../models/archive_format.rs"]
+mod archive_format;
+#[path = "../services/key_service.rs"]
+mod key_service;
 #[path = "./mod_plugin_controller/mod_plugin_service.rs"]
 mod mod_plugin_service;
 #[path = "../models/plugins.rs"]
 mod plugins;
+#[path = "../services/remote_command.rs"]
+mod remote_command;
 #[path = "../services/ssh_service.rs"]
 pub mod ssh_service;
-use mod_plugin_service::{
-    convert_to_path_object, derive_destination_folder_path, extract_root_folder_name,
+use crate::settings_controller::{
+    self, get_pinned_host_key, pin_host_key, resolve_mod_device, unpin_host_key, ModDevice,
+    PrivateKey,
 };
+use key_service::KeyService;
+use mod_plugin_service::{convert_to_path_object, extract_root_folder_name};
 pub use plugins::ArrayBufferWithPath;
-pub use ssh_service::{SshError, SshService};
-use std::path::Path;
+use plugins::{find_path_through_link, is_link_inside};
+use remote_command::{get_plugin_path, RemoteCommand, RemoteCommandError};
+pub use ssh_service::{SshAuthentication, SshError, SshService};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tauri::Manager;
 use thiserror::Error;
 
+const RECEIPTS_FILE: &str = ".dm-plugin-manager.json";
+
 #[derive(Error, Debug)]
 pub enum Error {
     #[error("{0}")]
     Ssh(#[from] SshError),
 
+    #[error("{0}")]
+    Settings(#[from] settings_controller::Error),
+
+    #[error("{0}")]
+    Tauri(#[from] tauri::Error),
+
+    #[error("{0}")]
+    RemoteCommand(#[from] RemoteCommandError),
+
     #[error("No plugins could be found.")]
     NoPlugins,
+
+    #[error("{0} links outside of the plugin bundle")]
+    UnsafeSymlink(String),
 }
 
 impl serde::Serialize for Error {
@@ -31,9 +58,149 @@ impl serde::Serialize for Error {
 }
 
 #[tauri::command]
-pub async fn get_mod_plugins() -> Result<Vec<String>, Error> {
-    let ssh_service = SshService::connect("192.168.51.1", "root", "mod").await?;
-    let stdout = ssh_service.execute_command("ls .lv2", None).await?;
+pub async fn get_mod_plugins(
+    device: Option<ModDevice>,
+    handle: tauri::AppHandle,
+) -> Result<Vec<String>, Error> {
+    let device = resolve_mod_device(device, &handle)?;
+    let ssh_service = connect(&device, &handle).await?;
+    let result = list_mod_plugins(&ssh_service).await;
+
+    close_session(&ssh_service, result).await
+}
+
+#[tauri::command]
+pub async fn create_mod_plugins(
+    files: Vec<ArrayBufferWithPath>,
+    device: Option<ModDevice>,
+    handle: tauri::AppHandle,
+) -> Result<Vec<String>, Error> {
+    let device = resolve_mod_device(device, &handle)?;
+    let ssh_service = connect(&device, &handle).await?;
+    let result = upload_mod_plugins(&ssh_service, files).await;
+
+    close_session(&ssh_service, result).await
+}
+
+#[tauri::command]
+pub async fn delete_mod_plugin(
+    name: String,
+    device: Option<ModDevice>,
+    handle: tauri::AppHandle,
+) -> Result<(), Error> {
+    let device = resolve_mod_device(device, &handle)?;
+    let ssh_service = connect(&device, &handle).await?;
+    let result = remove_mod_plugin(&ssh_service, name).await;
+
+    close_session(&ssh_service, result).await
+}
+
+#[tauri::command]
+pub async fn get_mod_host_key(
+    device: Option<ModDevice>,
+    handle: tauri::AppHandle,
+) -> Result<Option<String>, Error> {
+    let device = resolve_mod_device(device, &handle)?;
+    let fingerprint = get_pinned_host_key(&device, &handle)?;
+
+    Ok(fingerprint)
+}
+
+#[tauri::command]
+pub async fn reset_mod_host_key(
+    device: Option<ModDevice>,
+    handle: tauri::AppHandle,
+) -> Result<(), Error> {
+    let device = resolve_mod_device(device, &handle)?;
+    unpin_host_key(&device, &handle)?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_app_public_key(handle: tauri::AppHandle) -> Result<String, Error> {
+    let key_pair = get_key_service(&handle)?.load_or_generate()?;
+    let public_key = KeyService::public_key_line(&key_pair)?;
+
+    Ok(public_key)
+}
+
+#[tauri::command]
+pub async fn install_mod_public_key(
+    device: Option<ModDevice>,
+    handle: tauri::AppHandle,
+) -> Result<String, Error> {
+    let device = resolve_mod_device(device, &handle)?;
+    let key_pair = get_key_service(&handle)?.load_or_generate()?;
+    let public_key = KeyService::public_key_line(&key_pair)?;
+    // Remote commands run in the home folder of the user, so relative paths point to ~/.ssh
+    let install_command = RemoteCommand::new("mkdir")
+        .args(["-p", ".ssh"])
+        .and(RemoteCommand::new("chmod").args(["700", ".ssh"]))
+        .and(RemoteCommand::new("touch").arg(".ssh/authorized_keys"))
+        .and(RemoteCommand::new("chmod").args(["600", ".ssh/authorized_keys"]))
+        .and(
+            RemoteCommand::new("grep")
+                .args(["-qxF", &public_key, ".ssh/authorized_keys"])
+                .or(RemoteCommand::new("echo")
+                    .arg(&public_key)
+                    .append_to(".ssh/authorized_keys")),
+        );
+
+    let ssh_service = connect(&device, &handle).await?;
+    let result = ssh_service
+        .execute_command(&install_command.to_string(), None)
+        .await
+        .map_err(Error::from);
+    close_session(&ssh_service, result).await?;
+
+    Ok(public_key)
+}
+
+pub async fn connect(device: &ModDevice, handle: &tauri::AppHandle) -> Result<SshService, Error> {
+    let pinned_fingerprint = get_pinned_host_key(device, handle)?;
+    let authentication = match &device.private_key {
+        Some(PrivateKey::File { path, passphrase }) => {
+            SshAuthentication::PublicKey(Arc::new(KeyService::load(path, passphrase.as_deref())?))
+        }
+        Some(PrivateKey::App) => {
+            SshAuthentication::PublicKey(Arc::new(get_key_service(handle)?.load_or_generate()?))
+        }
+        None => SshAuthentication::Password(&device.password),
+    };
+    let ssh_service = SshService::connect(
+        &device.host,
+        device.port,
+        &device.username,
+        authentication,
+        pinned_fingerprint.as_deref(),
+    )
+    .await?;
+
+    // Trust on first use: any other machine answering on this address is rejected from now on
+    if pinned_fingerprint.is_none() {
+        pin_host_key(device, ssh_service.host_key_fingerprint(), handle)?;
+    }
+
+    Ok(ssh_service)
+}
+
+pub async fn close_session<T, E>(ssh_service: &SshService, result: Result<T, E>) -> Result<T, E>
+where
+    E: From<SshError>,
+{
+    let disconnect_result = ssh_service.disconnect().await;
+    let value = result?;
+    disconnect_result?;
+
+    Ok(value)
+}
+
+pub async fn list_mod_plugins(ssh_service: &SshService) -> Result<Vec<String>, Error> {
+    let command = RemoteCommand::new("ls").arg(RemoteCommand::PLUGIN_FOLDER);
+    let stdout = ssh_service
+        .execute_command(&command.to_string(), None)
+        .await?;
     if stdout.is_empty() {
         Err(Error::NoPlugins)
     } else {
@@ -42,51 +209,120 @@ pub async fn get_mod_plugins() -> Result<Vec<String>, Error> {
             .map(|item| item.to_string())
             .filter(|item| !item.is_empty())
             .collect();
-        ssh_service.disconnect().await?;
         Ok(plugins)
     }
 }
 
-#[tauri::command]
-pub async fn create_mod_plugins(files: Vec<ArrayBufferWithPath>) -> Result<Vec<String>, Error> {
+pub async fn upload_mod_plugins(
+    ssh_service: &SshService,
+    files: Vec<ArrayBufferWithPath>,
+) -> Result<Vec<String>, Error> {
     let mut plugin_names: Vec<String> = Vec::new();
-    let ssh_service = SshService::connect("192.168.51.1", "root", "mod").await?;
-
-    for file in files {
-        let path = &convert_to_path_object(file.path);
-        let destination_path = Path::new(".lv2").join(path);
-        let destination_folder_path = derive_destination_folder_path(&destination_path);
-        let mkdir_command = format!("mkdir -p {}", destination_folder_path);
-        let cat_command = format!("cat > {}", destination_path.to_string_lossy());
-
-        ssh_service.execute_command(&mkdir_command, None).await?;
-        ssh_service
-            .execute_command(&cat_command, Some(&file.buffer))
-            .await?;
 
+    for file in &files {
+        let path = &convert_to_path_object(file.path.clone());
         let created_plugin_name = extract_root_folder_name(path);
         if !plugin_names.contains(&created_plugin_name) {
             plugin_names.push(created_plugin_name)
         }
     }
 
-    ssh_service.disconnect().await?;
+    upload_mod_bundle(ssh_service, &files).await?;
 
     Ok(plugin_names)
 }
 
-#[tauri::command]
-pub async fn delete_mod_plugin(name: String) -> Result<(), Error> {
-    let ssh_service = SshService::connect("192.168.51.1", "root", "mod").await?;
+pub async fn upload_mod_bundle(
+    ssh_service: &SshService,
+    files: &[ArrayBufferWithPath],
+) -> Result<u64, Error> {
+    validate_mod_bundle(files)?;
+    let bytes_transferred = ssh_service
+        .upload_files(RemoteCommand::PLUGIN_FOLDER, files)
+        .await?;
+
+    Ok(bytes_transferred)
+}
+
+pub fn validate_mod_bundle(files: &[ArrayBufferWithPath]) -> Result<(), Error> {
+    for file in files {
+        let path = file.path.trim_start_matches("/");
+        get_plugin_path(path)?;
+        if file.is_symlink() {
+            let path = Path::new(path);
+            let bundle_path: PathBuf = path.components().take(1).collect();
+            let target = String::from_utf8_lossy(&file.buffer);
+            if !is_link_inside(path, Path::new(target.as_ref()), &bundle_path) {
+                return Err(Error::UnsafeSymlink(file.path.clone()));
+            }
+        }
+    }
+
+    let entries: Vec<_> = files
+        .iter()
+        .map(|file| {
+            let path = PathBuf::from(file.path.trim_start_matches("/"));
+            let target = file
+                .is_symlink()
+                .then(|| PathBuf::from(String::from_utf8_lossy(&file.buffer).as_ref()));
+            (path, target)
+        })
+        .collect();
+    if let Some(path) = find_path_through_link(&entries) {
+        return Err(Error::UnsafeSymlink(path.to_string_lossy().to_string()));
+    }
+
+    Ok(())
+}
+
+pub async fn remove_mod_plugin(ssh_service: &SshService, name: String) -> Result<(), Error> {
+    let command = RemoteCommand::new("rm").arg("-rf").plugin_path_arg(&name)?;
+    ssh_service
+        .execute_command(&command.to_string(), None)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn read_mod_receipts(ssh_service: &SshService) -> Result<String, Error> {
+    let receipts_path = format!("{}/{}", RemoteCommand::PLUGIN_FOLDER, RECEIPTS_FILE);
+    let command = RemoteCommand::new("test")
+        .arg("-f")
+        .arg(&receipts_path)
+        .and(RemoteCommand::new("cat").arg(&receipts_path))
+        .or(RemoteCommand::new("true"));
+    let stdout = ssh_service
+        .execute_command(&command.to_string(), None)
+        .await?;
+
+    Ok(stdout)
+}
+
+pub async fn write_mod_receipts(ssh_service: &SshService, contents: &[u8]) -> Result<(), Error> {
+    let receipts_path = format!("{}/{}", RemoteCommand::PLUGIN_FOLDER, RECEIPTS_FILE);
+    let temp_path = format!("{}.tmp", receipts_path);
+    let command = RemoteCommand::new("cat")
+        .write_to(&temp_path)
+        .and(RemoteCommand::new("mv").arg(&temp_path).arg(&receipts_path));
     ssh_service
-        .execute_command(&format!("rm -rf .lv2/{}", name), None)
+        .execute_command(&command.to_string(), Some(contents))
         .await?;
 
     Ok(())
 }
 
-pub async fn establish_connection() -> Result<(), SshError> {
-    SshService::connect("192.168.51.1", "root", "mod").await?;
+pub async fn establish_connection(
+    device: &ModDevice,
+    handle: &tauri::AppHandle,
+) -> Result<(), Error> {
+    let ssh_service = connect(device, handle).await?;
+    ssh_service.disconnect().await?;
 
     Ok(())
 }
+
+fn get_key_service(handle: &tauri::AppHandle) -> Result<KeyService, Error> {
+    let config_folder = handle.path().app_config_dir()?;
+
+    Ok(KeyService::new(config_folder))
+}
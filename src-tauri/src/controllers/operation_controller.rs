@@ -0,0 +1,12 @@
+#[path = "../services/operation_service.rs"]
+mod operation_service;
+pub use operation_service::OperationService;
+pub use tokio_util::sync::CancellationToken;
+
+#[tauri::command]
+pub fn cancel_operation(
+    operation_id: String,
+    operations: tauri::State<'_, OperationService>,
+) -> bool {
+    operations.cancel(&operation_id)
+}
@@ -1,33 +1,86 @@
+#[path = "../models/archive_format.rs"]
+mod archive_format;
+#[path = "../services/archive_service.rs"]
+mod archive_service;
+#[path = "../services/cache_service.rs"]
+mod cache_service;
+#[path = "../models/catalog_cache.rs"]
+mod catalog_cache;
+#[path = "../services/catalog_service.rs"]
+mod catalog_service;
+#[path = "./plugin_controller/check_updates_service.rs"]
+mod check_updates_service;
 #[path = "./plugin_controller/create_plugins_service.rs"]
 mod create_plugins_service;
 #[path = "./plugin_controller/delete_plugins_service.rs"]
 mod delete_plugins_service;
+#[path = "../models/download_cache.rs"]
+mod download_cache;
+#[path = "../services/download_service.rs"]
+mod download_service;
 #[path = "./plugin_controller/get_plugins_service.rs"]
 mod get_plugins_service;
+#[path = "../models/github_release.rs"]
+mod github_release;
+#[path = "../services/github_service.rs"]
+mod github_service;
+#[path = "../services/http_service.rs"]
+mod http_service;
+#[path = "../models/install_receipt.rs"]
+mod install_receipt;
+#[path = "../services/layout_service.rs"]
+mod layout_service;
 #[path = "../models/mod_platform.rs"]
 mod mod_platform;
 #[path = "../models/plugin_format.rs"]
 mod plugin_format;
+#[path = "../models/plugin_update.rs"]
+mod plugin_update;
 #[path = "../models/plugins.rs"]
 mod plugins;
+#[path = "../models/progress.rs"]
+mod progress;
+#[path = "../services/progress_service.rs"]
+mod progress_service;
+#[path = "../services/receipt_service.rs"]
+mod receipt_service;
+#[path = "../services/signature_service.rs"]
+mod signature_service;
+#[path = "../services/tar_service.rs"]
+mod tar_service;
 #[path = "./plugin_controller/utils.rs"]
 pub mod utils;
 #[path = "../services/zip_service.rs"]
 mod zip_service;
+use cache_service::CacheService;
+use catalog_service::CatalogService;
+use check_updates_service::get_plugin_updates;
 use create_plugins_service::{
-    create_mod_plugins, create_plugin_folders_on_mac_os, create_vst_or_clap_plugins,
-    remove_plugin_folders_on_mac_os,
+    create_mod_plugins, create_vst_or_clap_plugins, install_staged_plugins_on_mac_os,
+    remove_staged_plugins,
 };
 use delete_plugins_service::{delete_mod_plugins, delete_vst_or_clap_plugins};
-use get_plugins_service::{get_installed_mod_plugins, get_installed_vst_or_clap_plugins};
+use download_cache::CacheInfo;
+use download_service::DownloadService;
+use get_plugins_service::{
+    get_available_plugins, get_installed_mod_plugins, get_installed_vst_or_clap_plugins,
+};
+use github_service::GithubService;
+pub use http_service::HttpService;
 use mod_platform::ModPlatform;
 use plugin_format::PluginFormat;
-use plugins::{GetPluginsResponse, PluginsConfig, SelectedPlugins};
-use std::fs::File;
+use plugin_update::PluginUpdate;
+use plugins::{GetPluginsResponse, PluginRelease, PluginsConfig, SelectedPlugin, SelectedPlugins};
+use std::collections::{HashMap, HashSet};
 use tauri::{path::BaseDirectory, Manager};
 use thiserror::Error;
+use utils::check_cancelled;
 
 use crate::mod_plugin_controller::{self, ssh_service::SshError};
+use crate::operation_controller::OperationService;
+use crate::settings_controller::{
+    self, load_settings, resolve_mod_device, CacheSettings, ModDevice,
+};
 
 #[derive(Error, Debug)]
 pub enum Error {
@@ -46,6 +99,9 @@ pub enum Error {
     #[error("{0}")]
     ModPluginControllerError(#[from] mod_plugin_controller::Error),
 
+    #[error("{0}")]
+    SettingsControllerError(#[from] settings_controller::Error),
+
     #[error("Could not find a plugin folder for this operating system and plugin format")]
     NoPluginFolder,
 
@@ -60,6 +116,70 @@ pub enum Error {
 
     #[error("Unable to create directory: {0}")]
     CreateDirectoryError(String),
+
+    #[error("Could not find {0}")]
+    NotFound(String),
+
+    #[error("Too many requests, try again later")]
+    RateLimited,
+
+    #[error("Network error: {0}")]
+    NetworkError(String),
+
+    #[error("The server responded with status {0}")]
+    HttpError(u16),
+
+    #[error("Invalid proxy url: {0}")]
+    InvalidProxy(String),
+
+    #[error("Could not load the certificates in {0}")]
+    InvalidCertificate(String),
+
+    #[error("Could not find a release for {0}")]
+    NoReleaseFound(String),
+
+    #[error("Invalid plugin catalog: {0}")]
+    InvalidCatalog(String),
+
+    #[error("Invalid release tag: {0}")]
+    InvalidReleaseTag(String),
+
+    #[error("Checksum mismatch for {asset}: expected {expected}, got {actual}")]
+    ChecksumMismatch {
+        asset: String,
+        expected: String,
+        actual: String,
+    },
+
+    #[error("No checksum is available for {0}")]
+    MissingChecksum(String),
+
+    #[error("Invalid checksum file for {0}")]
+    InvalidChecksum(String),
+
+    #[error("No signature is available for {0}")]
+    MissingSignature(String),
+
+    #[error("Invalid signature file for {0}")]
+    InvalidSignature(String),
+
+    #[error("The signature of {0} doesn't match any of the trusted keys")]
+    UntrustedSignature(String),
+
+    #[error("The downloaded plugin bundle {0} is empty or missing")]
+    InvalidPluginBundle(String),
+
+    #[error("The archive {asset} has an unexpected layout: {reason}")]
+    InvalidArchiveLayout { asset: String, reason: String },
+
+    #[error("{plugin} is not available for the {architecture} architecture")]
+    UnsupportedArchitecture {
+        plugin: String,
+        architecture: String,
+    },
+
+    #[error("The operation was cancelled")]
+    Cancelled,
 }
 
 impl serde::Serialize for Error {
@@ -75,36 +195,16 @@ impl serde::Serialize for Error {
 pub async fn get_installable_plugins(
     plugin_formats: Vec<String>,
     mod_platform: Option<ModPlatform>,
+    mod_device: Option<ModDevice>,
     handle: tauri::AppHandle,
 ) -> Result<GetPluginsResponse, Error> {
-    let file_path = handle
-        .path()
-        .resolve("resources/dm-plugins.json", BaseDirectory::Resource)?;
-    let file = File::open(&file_path)?;
-    let config: PluginsConfig = serde_json::from_reader(file)?;
-    let mut response = GetPluginsResponse::default();
-
-    if plugin_formats.contains(&PluginFormat::VST3.to_string()) {
-        response.vst3 = config.vst3;
-    }
-
-    if plugin_formats.contains(&PluginFormat::CLAP.to_string()) {
-        response.clap = config.clap;
-    }
-
-    if plugin_formats.contains(&PluginFormat::ModAudio.to_string()) {
-        match mod_platform {
-            Some(ModPlatform::Duo) => response.mod_audio = config.mod_audio.duo,
-            Some(ModPlatform::DuoX) => response.mod_audio = config.mod_audio.duo_x,
-            Some(ModPlatform::Dwarf) => response.mod_audio = config.mod_audio.dwarf,
-            None => (),
-        };
-    }
+    let mut response = get_catalog_plugins(&plugin_formats, mod_platform, &handle).await?;
 
     if plugin_formats.contains(&PluginFormat::ModAudio.to_string()) {
-        let result = mod_plugin_controller::establish_connection().await;
+        let device = resolve_mod_device(mod_device, &handle)?;
+        let result = mod_plugin_controller::establish_connection(&device, &handle).await;
         match result {
-            Err(SshError::NoConnection) => {
+            Err(mod_plugin_controller::Error::Ssh(SshError::NoConnection)) => {
                 response.mod_is_connected = false;
                 Ok(())
             }
@@ -124,11 +224,11 @@ pub async fn get_installed_plugins(
     vst3_folder: Option<String>,
     clap_folder: Option<String>,
     mod_platform: Option<ModPlatform>,
+    mod_device: Option<ModDevice>,
     handle: tauri::AppHandle,
 ) -> Result<GetPluginsResponse, Error> {
     let mut installed_plugins = GetPluginsResponse::default();
-    let installable_plugins =
-        get_installable_plugins(plugin_formats.clone(), mod_platform, handle).await?;
+    let installable_plugins = get_catalog_plugins(&plugin_formats, mod_platform, &handle).await?;
 
     get_installed_vst_or_clap_plugins(
         &plugin_formats,
@@ -146,14 +246,90 @@ pub async fn get_installed_plugins(
         &mut installed_plugins,
     )?;
 
-    get_installed_mod_plugins(
-        &plugin_formats,
-        &installable_plugins,
-        &mut installed_plugins,
+    // The same session tells whether the device is connected and what's installed on it
+    if plugin_formats.contains(&PluginFormat::ModAudio.to_string()) {
+        let device = resolve_mod_device(mod_device, &handle)?;
+        let connection = mod_plugin_controller::connect(&device, &handle).await;
+        match connection {
+            Err(mod_plugin_controller::Error::Ssh(SshError::NoConnection)) => {
+                installed_plugins.mod_is_connected = false;
+            }
+            connection => {
+                let ssh_service = connection?;
+                installed_plugins.mod_is_connected = true;
+                let result = get_installed_mod_plugins(
+                    &ssh_service,
+                    &installable_plugins,
+                    &mut installed_plugins,
+                )
+                .await;
+                mod_plugin_controller::close_session(&ssh_service, result).await?;
+            }
+        }
+    }
+    installed_plugins.plugin_info = installable_plugins.plugin_info;
+
+    Ok(installed_plugins)
+}
+
+#[tauri::command]
+pub async fn check_plugin_updates(
+    plugin_formats: Vec<String>,
+    vst3_folder: Option<String>,
+    clap_folder: Option<String>,
+    mod_platform: Option<ModPlatform>,
+    mod_device: Option<ModDevice>,
+    handle: tauri::AppHandle,
+) -> Result<Vec<PluginUpdate>, Error> {
+    let installed_plugins = get_installed_plugins(
+        plugin_formats,
+        vst3_folder,
+        clap_folder,
+        mod_platform,
+        mod_device,
+        handle.clone(),
     )
     .await?;
+    let github_service = get_github_service(&handle)?;
 
-    Ok(installed_plugins)
+    Ok(get_plugin_updates(&installed_plugins, &github_service).await)
+}
+
+#[tauri::command]
+pub async fn get_plugin_releases(
+    plugin_name: String,
+    handle: tauri::AppHandle,
+) -> Result<Vec<PluginRelease>, Error> {
+    let github_service = get_github_service(&handle)?;
+    let releases = github_service
+        .get_releases(&plugin_name)
+        .await?
+        .into_iter()
+        .filter(|release| !release.draft)
+        .map(|release| PluginRelease {
+            version: GithubService::get_version(&release.tag_name),
+            tag: release.tag_name,
+            name: release.name,
+            published_at: release.published_at,
+            prerelease: release.prerelease,
+        })
+        .collect();
+
+    Ok(releases)
+}
+
+#[tauri::command]
+pub async fn get_cache_info(handle: tauri::AppHandle) -> Result<CacheInfo, Error> {
+    let settings = load_settings(&handle)?;
+    let cache_service = get_cache_service(&settings.cache, &handle)?;
+
+    Ok(cache_service.get_info())
+}
+
+#[tauri::command]
+pub async fn clear_cache(handle: tauri::AppHandle) -> Result<(), Error> {
+    let settings = load_settings(&handle)?;
+    get_cache_service(&settings.cache, &handle)?.clear()
 }
 
 #[tauri::command]
@@ -162,27 +338,64 @@ pub async fn create_plugins(
     vst3_folder: Option<String>,
     clap_folder: Option<String>,
     mod_platform: Option<String>,
+    mod_device: Option<ModDevice>,
+    operation_id: Option<String>,
+    operations: tauri::State<'_, OperationService>,
+    handle: tauri::AppHandle,
 ) -> Result<(), Error> {
-    create_plugin_folders_on_mac_os(&plugins, &vst3_folder, &clap_folder)?;
+    let token = operations.start(operation_id.as_deref());
+    let mut staged_plugins = Vec::new();
+    let result = async {
+        // The catalog is only fetched once for all formats
+        let download_service = get_download_service(&handle).await?;
+        let desktop_result = async {
+            create_vst_or_clap_plugins(
+                &plugins.vst3,
+                PluginFormat::VST3,
+                &vst3_folder,
+                &download_service,
+                &handle,
+                &token,
+                &mut staged_plugins,
+            )
+            .await?;
+            create_vst_or_clap_plugins(
+                &plugins.clap,
+                PluginFormat::CLAP,
+                &clap_folder,
+                &download_service,
+                &handle,
+                &token,
+                &mut staged_plugins,
+            )
+            .await
+        }
+        .await;
+        // The plugins that were staged before a failure still get installed, unless the user cancelled
+        check_cancelled(&token)?;
+        install_staged_plugins_on_mac_os(&staged_plugins)?;
+        desktop_result?;
 
-    if let Err(e) =
-        create_vst_or_clap_plugins(&plugins.vst3, PluginFormat::VST3, &vst3_folder).await
-    {
-        remove_plugin_folders_on_mac_os(&plugins.vst3, PluginFormat::VST3, &vst3_folder)?;
-        return Err(e);
-    }
-    if let Err(e) =
-        create_vst_or_clap_plugins(&plugins.clap, PluginFormat::CLAP, &clap_folder).await
-    {
-        remove_plugin_folders_on_mac_os(&plugins.clap, PluginFormat::CLAP, &clap_folder)?;
-        return Err(e);
-    }
+        if let Some(platform) = mod_platform {
+            let device = resolve_mod_device(mod_device, &handle)?;
+            create_mod_plugins(
+                plugins.mod_audio,
+                &platform,
+                &device,
+                &download_service,
+                &handle,
+                &token,
+            )
+            .await?;
+        }
 
-    if let Some(platform) = mod_platform {
-        create_mod_plugins(plugins.mod_audio, &platform).await?;
+        Ok::<(), Error>(())
     }
+    .await;
+    remove_staged_plugins(&staged_plugins);
+    operations.finish(operation_id.as_deref());
 
-    Ok(())
+    result
 }
 
 #[tauri::command]
@@ -190,10 +403,149 @@ pub async fn delete_plugins(
     plugins: SelectedPlugins,
     vst3_folder: Option<String>,
     clap_folder: Option<String>,
+    mod_device: Option<ModDevice>,
+    operation_id: Option<String>,
+    operations: tauri::State<'_, OperationService>,
+    handle: tauri::AppHandle,
 ) -> Result<(), Error> {
-    delete_vst_or_clap_plugins(plugins.vst3, PluginFormat::VST3, vst3_folder).await?;
-    delete_vst_or_clap_plugins(plugins.clap, PluginFormat::CLAP, clap_folder).await?;
-    delete_mod_plugins(plugins.mod_audio).await?;
+    let token = operations.start(operation_id.as_deref());
+    let result = async {
+        let vst3_plugins = SelectedPlugin::names(&plugins.vst3);
+        delete_vst_or_clap_plugins(vst3_plugins, PluginFormat::VST3, vst3_folder, &token).await?;
+        let clap_plugins = SelectedPlugin::names(&plugins.clap);
+        delete_vst_or_clap_plugins(clap_plugins, PluginFormat::CLAP, clap_folder, &token).await?;
+        if !plugins.mod_audio.is_empty() {
+            let device = resolve_mod_device(mod_device, &handle)?;
+            let mod_plugins = SelectedPlugin::names(&plugins.mod_audio);
+            delete_mod_plugins(mod_plugins, &device, &handle, &token).await?;
+        }
+
+        Ok::<(), Error>(())
+    }
+    .await;
+    operations.finish(operation_id.as_deref());
+
+    result
+}
+
+fn get_http_client(handle: &tauri::AppHandle) -> Result<reqwest::Client, Error> {
+    let settings = load_settings(handle)?;
+
+    handle.state::<HttpService>().get_client(&settings.network)
+}
+
+fn get_github_service(handle: &tauri::AppHandle) -> Result<GithubService, Error> {
+    let settings = load_settings(handle)?;
+
+    Ok(GithubService::new(
+        &settings.github,
+        get_http_client(handle)?,
+    ))
+}
+
+async fn get_catalog_plugins(
+    plugin_formats: &Vec<String>,
+    mod_platform: Option<ModPlatform>,
+    handle: &tauri::AppHandle,
+) -> Result<GetPluginsResponse, Error> {
+    let config = load_catalog(handle, get_http_client(handle)?).await?;
+    let app_version = handle.package_info().version.to_string();
+    let mut response = GetPluginsResponse::default();
+    let plugin_info = &mut response.plugin_info;
+
+    if plugin_formats.contains(&PluginFormat::VST3.to_string()) {
+        response.vst3 = get_available_plugins(config.vst3, &app_version, true, plugin_info);
+    }
+
+    if plugin_formats.contains(&PluginFormat::CLAP.to_string()) {
+        response.clap = get_available_plugins(config.clap, &app_version, true, plugin_info);
+    }
+
+    if plugin_formats.contains(&PluginFormat::ModAudio.to_string()) {
+        let mod_plugins = match mod_platform {
+            Some(ModPlatform::Duo) => config.mod_audio.duo,
+            Some(ModPlatform::DuoX) => config.mod_audio.duo_x,
+            Some(ModPlatform::Dwarf) => config.mod_audio.dwarf,
+            None => Vec::new(),
+        };
+        response.mod_audio = get_available_plugins(mod_plugins, &app_version, false, plugin_info);
+    }
+
+    Ok(response)
+}
+
+async fn load_catalog(
+    handle: &tauri::AppHandle,
+    client: reqwest::Client,
+) -> Result<PluginsConfig, Error> {
+    let settings = load_settings(handle)?;
+    let cache_folder = handle.path().app_cache_dir()?;
+    let bundled_catalog_path = handle
+        .path()
+        .resolve("resources/dm-plugins.json", BaseDirectory::Resource)?;
+
+    CatalogService::new(cache_folder, settings.catalog.url, client)
+        .load(&bundled_catalog_path)
+        .await
+}
+
+async fn get_download_service(handle: &tauri::AppHandle) -> Result<DownloadService, Error> {
+    let settings = load_settings(handle)?;
+    let client = get_http_client(handle)?;
+    let github_service = GithubService::new(&settings.github, client.clone());
+    let cache_service = get_cache_service(&settings.cache, handle)?;
+    let config = load_catalog(handle, client).await?;
+    let mut checksums = HashMap::new();
+    let mut archive_formats = HashMap::new();
+    let mut architectures = HashMap::new();
+    let mut signed_plugins = HashSet::new();
+    let entries = config
+        .vst3
+        .into_iter()
+        .chain(config.clap)
+        .chain(config.mod_audio.duo)
+        .chain(config.mod_audio.duo_x)
+        .chain(config.mod_audio.dwarf);
+    for entry in entries {
+        if let Some(archive_format) = entry.archive_format {
+            archive_formats
+                .entry(entry.name.clone())
+                .or_insert(archive_format);
+        }
+        if let Some(entry_architectures) = entry.architectures {
+            architectures
+                .entry(entry.name.clone())
+                .or_insert(entry_architectures);
+        }
+        if entry.signed {
+            signed_plugins.insert(entry.name.clone());
+        }
+        checksums
+            .entry(entry.name)
+            .or_insert_with(HashMap::new)
+            .extend(entry.checksums);
+    }
+
+    Ok(DownloadService::new(
+        github_service,
+        cache_service,
+        settings.verification,
+        settings.network,
+        checksums,
+        archive_formats,
+        architectures,
+        signed_plugins,
+    ))
+}
+
+fn get_cache_service(
+    settings: &CacheSettings,
+    handle: &tauri::AppHandle,
+) -> Result<CacheService, Error> {
+    let cache_folder = handle.path().app_data_dir()?.join("download-cache");
 
-    Ok(())
+    Ok(CacheService::new(
+        cache_folder,
+        settings.max_size_mb * 1024 * 1024,
+    ))
 }
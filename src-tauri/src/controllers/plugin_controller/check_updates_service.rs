@@ -0,0 +1,67 @@
+use super::github_service::GithubService;
+use super::plugin_format::PluginFormat;
+use super::plugin_update::{PluginUpdate, UpdateStatus};
+use super::plugins::{GetPluginsResponse, PluginVersion};
+use super::utils::is_newer_version;
+use futures::future::join_all;
+use std::collections::HashMap;
+
+pub async fn get_plugin_updates(
+    installed_plugins: &GetPluginsResponse,
+    github_service: &GithubService,
+) -> Vec<PluginUpdate> {
+    let versions = &installed_plugins.installed_versions;
+    let installed: Vec<(PluginFormat, &Vec<String>, &HashMap<String, PluginVersion>)> = vec![
+        (PluginFormat::VST3, &installed_plugins.vst3, &versions.vst3),
+        (PluginFormat::CLAP, &installed_plugins.clap, &versions.clap),
+        (
+            PluginFormat::ModAudio,
+            &installed_plugins.mod_audio,
+            &versions.mod_audio,
+        ),
+    ];
+
+    // The same release serves every format, so each plugin is only looked up once
+    let mut plugin_names: Vec<&String> = installed
+        .iter()
+        .flat_map(|(_, plugins, _)| plugins.iter())
+        .collect();
+    plugin_names.sort();
+    plugin_names.dedup();
+    let tags: HashMap<&String, String> =
+        join_all(plugin_names.into_iter().map(|plugin| async move {
+            let tag = github_service.get_latest_release_tag(plugin).await.ok()?;
+            Some((plugin, tag))
+        }))
+        .await
+        .into_iter()
+        .flatten()
+        .collect();
+
+    installed
+        .iter()
+        .flat_map(|(plugin_format, plugins, versions)| {
+            plugins.iter().map(|plugin| {
+                let installed_version = versions.get(plugin).map(|version| version.version.clone());
+                let latest_tag = tags.get(plugin).cloned();
+                let latest_version = latest_tag.as_deref().map(GithubService::get_version);
+                let status = match (&installed_version, &latest_version) {
+                    (Some(installed), Some(latest)) if is_newer_version(latest, installed) => {
+                        UpdateStatus::UpdateAvailable
+                    }
+                    (Some(_), Some(_)) => UpdateStatus::UpToDate,
+                    _ => UpdateStatus::Unknown,
+                };
+
+                PluginUpdate {
+                    name: plugin.to_owned(),
+                    format: plugin_format.to_string(),
+                    installed_version,
+                    latest_version,
+                    latest_tag,
+                    status,
+                }
+            })
+        })
+        .collect()
+}
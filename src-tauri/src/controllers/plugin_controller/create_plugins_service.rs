@@ -1,95 +1,86 @@
+use super::archive_format::ArchiveFormat;
+use super::archive_service::ArchiveService;
+use super::download_service::{DownloadService, DownloadedFile};
+use super::github_service::GithubService;
+use super::install_receipt::InstallReceipt;
+use super::layout_service::LayoutService;
 use super::mod_platform::ModPlatform;
 use super::plugin_format::PluginFormat;
-use super::plugins::SelectedPlugins;
-use super::utils::{get_plugin_bundle_name, get_plugin_folder, get_plugin_path};
-use super::zip_service::ZipService;
+use super::plugins::SelectedPlugin;
+use super::progress::ProgressPhase;
+use super::progress_service::ProgressService;
+use super::receipt_service::ReceiptService;
+use super::utils::{
+    check_cancelled, get_backup_path, get_plugin_bundle_name, get_plugin_folder, get_plugin_path,
+    get_receipt_path, get_staging_path, partition_results, quote_path, remove_path,
+    run_cancellable, run_with_administrator_privileges,
+};
 use super::Error;
-use crate::mod_plugin_controller;
-use futures::future::try_join_all;
-use std::fs::{self, File};
-use std::io;
+use crate::mod_plugin_controller::{self, ArrayBufferWithPath, SshService};
+use crate::operation_controller::CancellationToken;
+use crate::settings_controller::ModDevice;
+use futures::future::join_all;
+use std::env::consts::ARCH;
+use std::fs;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tauri::utils::platform::Target;
+use tokio::time::timeout;
 
-pub fn create_plugin_folders_on_mac_os(
-    plugins: &SelectedPlugins,
-    vst3_folder: &Option<String>,
-    clap_folder: &Option<String>,
-) -> Result<(), Error> {
-    if Target::current() != Target::MacOS || (plugins.vst3.is_empty() && plugins.clap.is_empty()) {
-        return Ok(());
-    }
+const CLEANUP_TIMEOUT: Duration = Duration::from_secs(10);
 
-    let vst3_plugin_paths =
-        concatenate_plugin_paths(&plugins.vst3, PluginFormat::VST3, vst3_folder)?;
-    let clap_plugin_paths =
-        concatenate_plugin_paths(&plugins.clap, PluginFormat::CLAP, clap_folder)?;
-    let plugin_paths = format!("{vst3_plugin_paths} {clap_plugin_paths}");
-
-    let username_cmd = Command::new("id").arg("-un").output()?;
-    if username_cmd.status.success() {
-        let username = String::from_utf8_lossy(&username_cmd.stdout)
-            .trim()
-            .to_string();
-
-        let create_dir_script = format!(
-            r#"do shell script "mkdir -p {1} && chmod 755 {1} && chown -R {0}: {1}" with administrator privileges"#,
-            username,
-            plugin_paths.trim()
-        );
-        let create_dir_cmd = Command::new("osascript")
-            .arg("-e")
-            .arg(create_dir_script)
-            .output()?;
-
-        if create_dir_cmd.status.success() {
-            return Ok(());
-        } else {
-            return Err(Error::CreateDirectoryError(
-                String::from_utf8_lossy(&create_dir_cmd.stderr).to_string(),
-            ));
-        }
-    } else {
-        return Err(Error::CreateDirectoryError(
-            String::from_utf8_lossy(&username_cmd.stderr).to_string(),
-        ));
-    }
+// A bundle that is ready to be moved into the plugin folder
+pub struct StagedPlugin {
+    staging_path: PathBuf,
+    plugin_path: PathBuf,
 }
 
-pub fn remove_plugin_folders_on_mac_os(
-    plugins: &Vec<String>,
-    plugin_format: PluginFormat,
-    folder: &Option<String>,
-) -> Result<(), Error> {
-    if Target::current() != Target::MacOS {
+// The plugin folders on macOS are shared with other vendors and owned by root, so instead of
+// changing their owner the staged bundles and receipts are moved into place by one elevated script
+pub fn install_staged_plugins_on_mac_os(staged_plugins: &[StagedPlugin]) -> Result<(), Error> {
+    if staged_plugins.is_empty() {
         return Ok(());
     }
 
-    let plugin_paths = concatenate_plugin_paths(&plugins, plugin_format, folder)?;
+    let install_script = staged_plugins
+        .iter()
+        .map(|staged_plugin| {
+            let plugin_path = &staged_plugin.plugin_path;
+            let folder = quote_path(plugin_path.parent().unwrap_or(plugin_path));
+            let staging = quote_path(&staged_plugin.staging_path);
+            let plugin = quote_path(plugin_path);
+            let backup = quote_path(&get_backup_path(plugin_path));
+            let staged_receipt = quote_path(&get_receipt_path(&staged_plugin.staging_path));
+            let receipt = quote_path(&get_receipt_path(plugin_path));
+
+            format!(
+                "{{ [ -d {folder} ] || mkdir -p {folder}; }} && rm -rf {backup} && \
+                {{ [ ! -e {plugin} ] || mv {plugin} {backup}; }} && \
+                {{ mv {staging} {plugin} || {{ [ ! -e {backup} ] || mv {backup} {plugin}; false; }}; }} && \
+                rm -rf {backup} && mv -f {staged_receipt} {receipt}"
+            )
+        })
+        .collect::<Vec<String>>()
+        .join(" && ");
 
-    let remove_dir_script = format!(
-        r#"do shell script "rm -rf {}" with administrator privileges"#,
-        plugin_paths.trim()
-    );
-    let remove_dir_cmd = Command::new("osascript")
-        .arg("-e")
-        .arg(remove_dir_script)
-        .output()?;
+    run_with_administrator_privileges(&install_script)
+}
 
-    if remove_dir_cmd.status.success() {
-        return Ok(());
-    } else {
-        return Err(Error::CreateDirectoryError(
-            String::from_utf8_lossy(&remove_dir_cmd.stderr).to_string(),
-        ));
+pub fn remove_staged_plugins(staged_plugins: &[StagedPlugin]) {
+    for staged_plugin in staged_plugins {
+        let _ = remove_path(&staged_plugin.staging_path);
+        let _ = ReceiptService::remove(&staged_plugin.staging_path);
     }
 }
 
 pub async fn create_vst_or_clap_plugins(
-    plugins: &Vec<String>,
+    plugins: &Vec<SelectedPlugin>,
     target_plugin_format: PluginFormat,
     folder: &Option<String>,
+    download_service: &DownloadService,
+    handle: &tauri::AppHandle,
+    token: &CancellationToken,
+    staged_plugins: &mut Vec<StagedPlugin>,
 ) -> Result<(), Error> {
     if plugins.is_empty() {
         return Ok(());
@@ -108,126 +99,308 @@ pub async fn create_vst_or_clap_plugins(
             let plugin_format = target_plugin_format.clone();
 
             async move {
-                create_plugin(&plugin_folder, plugin.as_str(), plugin_format).await?;
-                Ok::<(), Error>(())
+                let progress = ProgressService::new(handle, plugin.name(), &plugin_format);
+                create_plugin(
+                    &plugin_folder,
+                    plugin,
+                    plugin_format,
+                    download_service,
+                    &progress,
+                    token,
+                )
+                .await
             }
         })
         .collect();
 
-    try_join_all(futures).await?;
+    // Every install runs to completion so a failing one can't abort the cleanup of the others
+    let (staged, first_error) = partition_results(join_all(futures).await);
+    staged_plugins.extend(staged.into_iter().flatten());
 
-    Ok(())
+    match first_error {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
 }
 
-pub async fn create_mod_plugins(plugins: Vec<String>, platform: &String) -> Result<(), Error> {
+pub async fn create_mod_plugins(
+    plugins: Vec<SelectedPlugin>,
+    platform: &String,
+    device: &ModDevice,
+    download_service: &DownloadService,
+    handle: &tauri::AppHandle,
+    token: &CancellationToken,
+) -> Result<(), Error> {
     if plugins.is_empty() {
         return Ok(());
     }
 
+    let ssh_service = run_cancellable(token, async {
+        Ok(mod_plugin_controller::connect(device, handle).await?)
+    })
+    .await?;
     let futures: Vec<_> = plugins
         .iter()
-        .map(|plugin| async move {
-            create_mod_plugin(plugin.as_str(), platform).await?;
-            Ok::<(), Error>(())
+        .map(|plugin| {
+            let ssh_service = &ssh_service;
+            async move {
+                let progress = ProgressService::new(handle, plugin.name(), &PluginFormat::ModAudio);
+                let receipt = create_mod_plugin(
+                    plugin,
+                    platform,
+                    ssh_service,
+                    download_service,
+                    &progress,
+                    token,
+                )
+                .await?;
+                Ok::<(String, InstallReceipt), Error>((plugin.name().to_string(), receipt))
+            }
         })
         .collect();
 
-    try_join_all(futures).await?;
+    // Every install runs to completion so a failing one can't abort the cleanup of the others,
+    // the plugins that did get installed are still recorded
+    let (receipts, first_error) = partition_results(join_all(futures).await);
+    let result = async {
+        ReceiptService::update_mod_receipts(&ssh_service, receipts, &[]).await?;
+        match first_error {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+    .await;
+    mod_plugin_controller::close_session(&ssh_service, result).await?;
 
     Ok(())
 }
 
-async fn create_mod_plugin(plugin_name: &str, mod_platform: &String) -> Result<(), Error> {
+async fn create_mod_plugin(
+    plugin: &SelectedPlugin,
+    mod_platform: &String,
+    ssh_service: &SshService,
+    download_service: &DownloadService,
+    progress: &ProgressService,
+    token: &CancellationToken,
+) -> Result<InstallReceipt, Error> {
     let mod_platform = map_mod_platform(mod_platform);
-    let zipfile_path = download_zip_file(plugin_name, mod_platform).await?;
-    let bundle_name = get_plugin_bundle_name(plugin_name, &PluginFormat::ModAudio)?;
-    let starts_with = match zipfile_path.with_extension("").file_name() {
-        Some(folder) => Ok(PathBuf::from(folder).join(&bundle_name)),
-        None => Err(Error::NoDownloadFile),
-    }?;
-
-    let unzip_result = ZipService::unzip_to_u8(&zipfile_path, &starts_with).map_err(Error::from);
-    let copy_result = match unzip_result {
-        Ok(files) => mod_plugin_controller::create_mod_plugins(files)
-            .await
-            .map_err(Error::from),
+    let archive_format = download_service.get_archive_format(plugin.name());
+    let asset = get_download_file_name(plugin.name(), mod_platform, archive_format)?;
+    let download = download_service
+        .download(plugin, &asset, progress, token)
+        .await?;
+    let archive_path = download.path.clone();
+    let bundle_name = get_plugin_bundle_name(plugin.name(), &PluginFormat::ModAudio)?;
+    let starts_with = get_bundle_prefix(&archive_path, &bundle_name)?;
+
+    progress.start(ProgressPhase::Extracting);
+    let extract_result = check_cancelled(token).and_then(|_| {
+        let files = ArchiveService::list_files(&archive_path)?;
+        LayoutService::validate(&files, &starts_with, &PluginFormat::ModAudio, &asset)?;
+        check_cancelled(token)?;
+        let files = ArchiveService::read_files(&archive_path, &starts_with)?;
+        // Checked before the upload, a rejected bundle must not remove the installed one
+        mod_plugin_controller::validate_mod_bundle(&files)?;
+        Ok(files)
+    });
+    let copy_result = match extract_result {
+        Ok(files) => upload_mod_files(&files, &bundle_name, ssh_service, progress, token).await,
         Err(e) => Err(e),
     };
     match copy_result {
-        Ok(_) => {
-            fs::remove_file(&zipfile_path)?;
-            return Ok(());
+        Ok(bytes_transferred) => {
+            fs::remove_file(&archive_path)?;
+            progress.report(
+                ProgressPhase::Uploading,
+                bytes_transferred,
+                Some(bytes_transferred),
+            );
+            progress.finish();
+            return Ok(create_receipt(&download));
         }
         Err(e) => {
-            fs::remove_file(&zipfile_path)?;
+            fs::remove_file(&archive_path)?;
             return Err(e);
         }
     }
 }
 
+async fn upload_mod_files(
+    files: &[ArrayBufferWithPath],
+    bundle_name: &str,
+    ssh_service: &SshService,
+    progress: &ProgressService,
+    token: &CancellationToken,
+) -> Result<u64, Error> {
+    check_cancelled(token)?;
+    progress.start(ProgressPhase::Uploading);
+
+    let result = run_cancellable(token, async {
+        Ok(mod_plugin_controller::upload_mod_bundle(ssh_service, files).await?)
+    })
+    .await;
+    if result.is_err() {
+        // Don't leave a half written bundle behind on the device
+        let _ = timeout(
+            CLEANUP_TIMEOUT,
+            mod_plugin_controller::remove_mod_plugin(ssh_service, bundle_name.to_string()),
+        )
+        .await;
+    }
+
+    result
+}
+
 async fn create_plugin(
     plugin_folder: &PathBuf,
-    plugin_name: &str,
+    plugin: &SelectedPlugin,
     plugin_format: PluginFormat,
-) -> Result<(), Error> {
-    let bundle_name = get_plugin_bundle_name(plugin_name, &plugin_format)?;
-    let plugin_path = get_plugin_path(plugin_folder, plugin_name, &plugin_format)?;
-    let zipfile_path = download_zip_file(plugin_name, None).await?;
-    let unzipped_folder = zipfile_path.with_extension("");
-
-    let unzip_result = ZipService::unzip(&zipfile_path).map_err(Error::from);
-    let copy_result = unzip_result.and_then(|_| {
-        copy_dir_all(unzipped_folder.join(&bundle_name), &plugin_path).map_err(Error::from)
+    download_service: &DownloadService,
+    progress: &ProgressService,
+    token: &CancellationToken,
+) -> Result<Option<StagedPlugin>, Error> {
+    let bundle_name = get_plugin_bundle_name(plugin.name(), &plugin_format)?;
+    let plugin_path = get_plugin_path(plugin_folder, plugin.name(), &plugin_format)?;
+    // The plugin folders on macOS aren't writable by the user, the bundle is staged
+    // in the temp folder and moved into place afterwards with administrator privileges
+    let is_mac_os = Target::current() == Target::MacOS;
+    let staging_path = if is_mac_os {
+        let staging_name = get_staging_path(&plugin_path);
+        std::env::temp_dir().join(staging_name.file_name().unwrap_or_default())
+    } else {
+        get_staging_path(&plugin_path)
+    };
+    // Linux assets are built per architecture, the other targets ship universal or x86_64 builds
+    if Target::current() == Target::Linux
+        && !download_service.supports_architecture(plugin.name(), ARCH)
+    {
+        return Err(Error::UnsupportedArchitecture {
+            plugin: plugin.name().to_string(),
+            architecture: ARCH.to_string(),
+        });
+    }
+    let archive_format = download_service.get_archive_format(plugin.name());
+    let asset = get_download_file_name(plugin.name(), None, archive_format)?;
+    let download = download_service
+        .download(plugin, &asset, progress, token)
+        .await?;
+    let archive_path = download.path.clone();
+    let bundle_prefix = get_bundle_prefix(&archive_path, &bundle_name)?;
+
+    progress.start(ProgressPhase::Extracting);
+    let install_result = check_cancelled(token).and_then(|_| {
+        let files = ArchiveService::list_files(&archive_path)?;
+        LayoutService::validate(&files, &bundle_prefix, &plugin_format, &asset)?;
+        check_cancelled(token)?;
+        stage_plugin(&archive_path, &bundle_prefix, &staging_path)?;
+        check_cancelled(token)?;
+        progress.start(ProgressPhase::Copying);
+        if is_mac_os {
+            ReceiptService::write(&staging_path, &create_receipt(&download))?;
+            return Ok(Some(StagedPlugin {
+                staging_path: staging_path.clone(),
+                plugin_path: plugin_path.clone(),
+            }));
+        }
+        swap_staged_plugin(&staging_path, &plugin_path)?;
+        ReceiptService::write(&plugin_path, &create_receipt(&download))?;
+        Ok(None)
     });
-    match copy_result {
-        Ok(_) => {
-            fs::remove_file(&zipfile_path)?;
-            fs::remove_dir_all(unzipped_folder)?;
-            return Ok(());
+    match install_result {
+        Ok(staged_plugin) => {
+            fs::remove_file(&archive_path)?;
+            progress.finish();
+            return Ok(staged_plugin);
         }
         Err(e) => {
-            fs::remove_file(&zipfile_path)?;
-            fs::remove_dir_all(unzipped_folder)?;
-            fs::remove_dir_all(&plugin_path)?;
+            // The existing install is left untouched, only remove what this attempt created
+            remove_path(&archive_path)?;
+            remove_path(&staging_path)?;
+            ReceiptService::remove(&staging_path)?;
             return Err(e);
         }
     }
 }
 
-async fn download_zip_file(
-    plugin_name: &str,
-    mod_platform: Option<ModPlatform>,
-) -> Result<PathBuf, Error> {
-    let download_file_name = get_download_file_name(plugin_name, mod_platform)?;
-    let url = format!(
-        "https://github.com/davemollen/{0}/releases/latest/download/{1}",
-        plugin_name, download_file_name
-    );
-    let response = reqwest::get(url).await?;
-    let zipfile_content = response.bytes().await?;
-    let zipfile_path = std::env::temp_dir().join(&download_file_name);
-    let mut zipfile = File::create(&zipfile_path)?;
-
-    match {
-        io::copy(&mut zipfile_content.as_ref(), &mut zipfile)?;
-        Ok(())
-    } {
-        Ok(()) => Ok(zipfile_path),
+// The bundle is written straight from the archive into the staging location
+fn stage_plugin(
+    archive_path: &PathBuf,
+    bundle_prefix: &Path,
+    staging_path: &Path,
+) -> Result<(), Error> {
+    remove_path(staging_path)?;
+    ArchiveService::extract_prefix(archive_path, bundle_prefix, staging_path)?;
+
+    let is_valid = match fs::metadata(staging_path) {
+        Ok(metadata) if metadata.is_dir() => fs::read_dir(staging_path)?.next().is_some(),
+        Ok(metadata) => metadata.len() > 0,
+        Err(_) => false,
+    };
+    if !is_valid {
+        let bundle_name = bundle_prefix.file_name().unwrap_or_default();
+        return Err(Error::InvalidPluginBundle(
+            bundle_name.to_string_lossy().to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+fn swap_staged_plugin(staging_path: &Path, plugin_path: &Path) -> Result<(), Error> {
+    let backup_path = get_backup_path(plugin_path);
+
+    // A previous swap got interrupted halfway, put the old install back first
+    if backup_path.exists() && !plugin_path.exists() {
+        fs::rename(&backup_path, plugin_path)?;
+    }
+    remove_path(&backup_path)?;
+
+    let has_backup = plugin_path.exists();
+    if has_backup {
+        fs::rename(plugin_path, &backup_path)?;
+    }
+
+    match fs::rename(staging_path, plugin_path) {
+        Ok(_) => {
+            if has_backup {
+                let _ = remove_path(&backup_path);
+            }
+            Ok(())
+        }
         Err(e) => {
-            fs::remove_file(&zipfile_path)?;
-            Err(e)
+            if has_backup {
+                fs::rename(&backup_path, plugin_path)?;
+            }
+            Err(e.into())
         }
     }
 }
 
+fn create_receipt(download: &DownloadedFile) -> InstallReceipt {
+    let installed_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+
+    InstallReceipt {
+        version: GithubService::get_version(&download.tag),
+        tag: download.tag.clone(),
+        asset: download.asset.clone(),
+        installed_at,
+        sha256: download.sha256.clone(),
+    }
+}
+
 fn get_download_file_name(
     plugin_name: &str,
     mod_platform: Option<ModPlatform>,
+    archive_format: ArchiveFormat,
 ) -> Result<String, Error> {
     let os = match (Target::current(), mod_platform) {
         (Target::MacOS, None) => Ok("vst-and-clap-macos".to_string()),
         (Target::Windows, None) => Ok("vst-and-clap-windows".to_string()),
-        (Target::Linux, None) => Ok("vst-and-clap-ubuntu".to_string()),
+        (Target::Linux, None) if ARCH == "x86_64" => Ok("vst-and-clap-ubuntu".to_string()),
+        (Target::Linux, None) => Ok(format!("vst-and-clap-ubuntu-{}", ARCH)),
         (_, Some(mod_platform)) => match mod_platform {
             ModPlatform::Dwarf => Ok("moddwarf-new".to_string()),
             ModPlatform::Duo => Ok("modduo-new".to_string()),
@@ -236,23 +409,19 @@ fn get_download_file_name(
         (_, None) => Err(Error::NoDownloadFile),
     }?;
 
-    Ok(format!("{0}-{1}.zip", plugin_name, os))
+    Ok(format!(
+        "{0}-{1}.{2}",
+        plugin_name,
+        os,
+        archive_format.extension()
+    ))
 }
 
-fn copy_dir_all(src: impl AsRef<Path>, dst: impl AsRef<Path>) -> Result<(), Error> {
-    fs::create_dir_all(&dst)?;
-
-    for entry in fs::read_dir(src)? {
-        let entry = entry?;
-        let file_type = entry.file_type()?;
-        if file_type.is_dir() {
-            copy_dir_all(entry.path(), dst.as_ref().join(entry.file_name()))?;
-        } else {
-            fs::copy(entry.path(), dst.as_ref().join(entry.file_name()))?;
-        }
+fn get_bundle_prefix(archive_path: &PathBuf, bundle_name: &str) -> Result<PathBuf, Error> {
+    match ArchiveService::get_root_folder(archive_path)?.file_name() {
+        Some(folder) => Ok(PathBuf::from(folder).join(bundle_name)),
+        None => Err(Error::NoDownloadFile),
     }
-
-    Ok(())
 }
 
 fn map_mod_platform(input: &String) -> Option<ModPlatform> {
@@ -263,36 +432,3 @@ fn map_mod_platform(input: &String) -> Option<ModPlatform> {
         _ => None,
     }
 }
-
-fn concatenate_plugin_paths(
-    plugins: &Vec<String>,
-    plugin_format: PluginFormat,
-    folder: &Option<String>,
-) -> Result<String, Error> {
-    let plugin_folder = if let Some(folder) = folder {
-        PathBuf::from(folder)
-    } else {
-        get_plugin_folder(&plugin_format)?
-    };
-
-    if !plugins.is_empty() {
-        let plugin_paths = plugins
-            .iter()
-            .map(|plugin| {
-                let path = get_plugin_path(&plugin_folder, plugin, &plugin_format)?;
-                Ok(path.to_str().unwrap_or_default().to_string())
-            })
-            .fold("".to_string(), |result, path: Result<String, Error>| {
-                match path {
-                    Err(_) => return result,
-                    Ok(path) => {
-                        return format!("{} {}", result, path).into();
-                    }
-                };
-            });
-
-        Ok(plugin_paths)
-    } else {
-        Ok("".to_string())
-    }
-}
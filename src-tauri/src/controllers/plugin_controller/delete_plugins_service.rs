@@ -1,16 +1,25 @@
 use super::{
     plugin_format::PluginFormat,
-    utils::{get_plugin_folder, get_plugin_path},
+    receipt_service::ReceiptService,
+    utils::{
+        check_cancelled, get_plugin_folder, get_plugin_path, get_receipt_path, quote_path,
+        run_cancellable, run_with_administrator_privileges,
+    },
     Error,
 };
-use crate::{mod_plugin_controller, plugin_controller::utils::get_plugin_bundle_name};
+use crate::{
+    mod_plugin_controller, operation_controller::CancellationToken,
+    plugin_controller::utils::get_plugin_bundle_name, settings_controller::ModDevice,
+};
 use futures::future::try_join_all;
 use std::{fs, path::PathBuf};
+use tauri::utils::platform::Target;
 
 pub async fn delete_vst_or_clap_plugins(
     plugins: Vec<String>,
     target_plugin_format: PluginFormat,
     folder: Option<String>,
+    token: &CancellationToken,
 ) -> Result<(), Error> {
     if plugins.is_empty() {
         return Ok(());
@@ -21,13 +30,19 @@ pub async fn delete_vst_or_clap_plugins(
     } else {
         get_plugin_folder(&target_plugin_format)?
     };
+    if Target::current() == Target::MacOS {
+        check_cancelled(token)?;
+        return remove_plugins_on_mac_os(&plugins, &plugin_folder, &target_plugin_format);
+    }
 
     let futures: Vec<_> = plugins
         .iter()
         .map(|plugin| {
             let plugin_folder = plugin_folder.clone();
+            let plugin_format = &target_plugin_format;
             async move {
-                delete_plugin(&plugin_folder, plugin.as_str(), &PluginFormat::VST3).await?;
+                check_cancelled(token)?;
+                delete_plugin(&plugin_folder, plugin.as_str(), plugin_format).await?;
                 Ok::<(), Error>(())
             }
         })
@@ -38,21 +53,41 @@ pub async fn delete_vst_or_clap_plugins(
     Ok(())
 }
 
-pub async fn delete_mod_plugins(plugins: Vec<String>) -> Result<(), Error> {
+pub async fn delete_mod_plugins(
+    plugins: Vec<String>,
+    device: &ModDevice,
+    handle: &tauri::AppHandle,
+    token: &CancellationToken,
+) -> Result<(), Error> {
     if plugins.is_empty() {
         return Ok(());
     }
 
+    let ssh_service = run_cancellable(token, async {
+        Ok(mod_plugin_controller::connect(device, handle).await?)
+    })
+    .await?;
     let futures: Vec<_> = plugins
         .iter()
-        .map(|plugin| async move {
-            let bundle_name = get_plugin_bundle_name(plugin.as_str(), &PluginFormat::ModAudio)?;
-            mod_plugin_controller::delete_mod_plugin(bundle_name).await?;
-            Ok::<(), Error>(())
+        .map(|plugin| {
+            let ssh_service = &ssh_service;
+            async move {
+                let bundle_name = get_plugin_bundle_name(plugin.as_str(), &PluginFormat::ModAudio)?;
+                run_cancellable(token, async {
+                    Ok(mod_plugin_controller::remove_mod_plugin(ssh_service, bundle_name).await?)
+                })
+                .await?;
+                Ok::<(), Error>(())
+            }
         })
         .collect();
 
-    try_join_all(futures).await?;
+    let result = async {
+        try_join_all(futures).await?;
+        ReceiptService::update_mod_receipts(&ssh_service, Vec::new(), &plugins).await
+    }
+    .await;
+    mod_plugin_controller::close_session(&ssh_service, result).await?;
 
     Ok(())
 }
@@ -64,6 +99,23 @@ async fn delete_plugin(
 ) -> Result<(), Error> {
     let plugin_path = get_plugin_path(plugin_folder, plugin_name, plugin_format)?;
     fs::remove_dir_all(&plugin_path)?;
+    ReceiptService::remove(&plugin_path)?;
 
     Ok(())
 }
+
+// The plugin folders on macOS are owned by root, so the bundles are removed with administrator privileges
+fn remove_plugins_on_mac_os(
+    plugins: &[String],
+    plugin_folder: &PathBuf,
+    plugin_format: &PluginFormat,
+) -> Result<(), Error> {
+    let mut plugin_paths = Vec::new();
+    for plugin in plugins {
+        let plugin_path = get_plugin_path(plugin_folder, plugin, plugin_format)?;
+        plugin_paths.push(quote_path(&plugin_path));
+        plugin_paths.push(quote_path(&get_receipt_path(&plugin_path)));
+    }
+
+    run_with_administrator_privileges(&format!("rm -rf {}", plugin_paths.join(" ")))
+}
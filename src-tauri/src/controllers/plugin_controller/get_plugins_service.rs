@@ -1,11 +1,51 @@
 use super::{
+    download_service::DownloadService,
     plugin_format::PluginFormat,
-    plugins::GetPluginsResponse,
-    utils::{get_plugin_bundle_name, get_plugin_folder, get_plugin_path},
+    plugins::{GetPluginsResponse, PluginEntry, PluginVersion},
+    receipt_service::ReceiptService,
+    utils::{get_plugin_bundle_name, get_plugin_folder, get_plugin_path, is_newer_version},
     Error,
 };
-use crate::mod_plugin_controller::{self, SshError};
+use crate::mod_plugin_controller::{self, SshService};
+use std::collections::HashMap;
+use std::env::consts::ARCH;
 use std::path::{Path, PathBuf};
+use tauri::utils::platform::Target;
+
+pub fn get_available_plugins(
+    entries: Vec<PluginEntry>,
+    app_version: &str,
+    check_platform: bool,
+    plugin_info: &mut HashMap<String, PluginEntry>,
+) -> Vec<String> {
+    let platform = get_platform_name();
+
+    entries
+        .into_iter()
+        .filter(|entry| match &entry.min_app_version {
+            Some(min_app_version) => !is_newer_version(min_app_version, app_version),
+            None => true,
+        })
+        .filter(|entry| match (&entry.platforms, check_platform) {
+            (Some(platforms), true) => platforms
+                .iter()
+                .any(|name| name.eq_ignore_ascii_case(platform)),
+            _ => true,
+        })
+        // Same rule as the install, so plugins without an asset for this architecture aren't offered
+        .filter(|entry| match (Target::current(), check_platform) {
+            (Target::Linux, true) => {
+                DownloadService::is_supported_architecture(entry.architectures.as_ref(), ARCH)
+            }
+            _ => true,
+        })
+        .map(|entry| {
+            let name = entry.name.clone();
+            plugin_info.entry(name.clone()).or_insert(entry);
+            name
+        })
+        .collect()
+}
 
 pub fn get_installed_vst_or_clap_plugins(
     plugin_formats: &Vec<String>,
@@ -44,9 +84,25 @@ pub fn get_installed_vst_or_clap_plugins(
         })
         .collect();
 
+    let found_versions: HashMap<String, PluginVersion> = found_plugins
+        .iter()
+        .filter_map(|plugin| {
+            let plugin_path =
+                get_plugin_path(&plugin_folder, plugin, &target_plugin_format).ok()?;
+            ReceiptService::get_installed_version(&plugin_path)
+                .map(|version| (plugin.to_owned(), version))
+        })
+        .collect();
+
     match target_plugin_format {
-        PluginFormat::VST3 => installed_plugins.vst3 = found_plugins,
-        PluginFormat::CLAP => installed_plugins.clap = found_plugins,
+        PluginFormat::VST3 => {
+            installed_plugins.vst3 = found_plugins;
+            installed_plugins.installed_versions.vst3 = found_versions;
+        }
+        PluginFormat::CLAP => {
+            installed_plugins.clap = found_plugins;
+            installed_plugins.installed_versions.clap = found_versions;
+        }
         _ => return Ok(()),
     };
 
@@ -54,30 +110,17 @@ pub fn get_installed_vst_or_clap_plugins(
 }
 
 pub async fn get_installed_mod_plugins(
-    plugin_formats: &Vec<String>,
+    ssh_service: &SshService,
     installable_plugins: &GetPluginsResponse,
     installed_plugins: &mut GetPluginsResponse,
 ) -> Result<(), Error> {
-    let plugin_format_key = PluginFormat::ModAudio.to_string();
-    if !plugin_formats.contains(&plugin_format_key) {
-        return Ok(());
-    }
     let plugins = &installable_plugins.mod_audio;
     if plugins.is_empty() {
         return Ok(());
     }
 
-    let result = mod_plugin_controller::get_mod_plugins().await;
-    let all_plugins = match result {
-        Err(mod_plugin_controller::Error::Ssh(SshError::NoConnection)) => {
-            installed_plugins.mod_is_connected = false;
-            return Ok(());
-        }
-        _ => {
-            installed_plugins.mod_is_connected = true;
-            result
-        }
-    }?;
+    let all_plugins = mod_plugin_controller::list_mod_plugins(ssh_service).await?;
+    let mut receipts = ReceiptService::read_mod_receipts(ssh_service).await?;
 
     installed_plugins.mod_audio = plugins
         .iter()
@@ -90,6 +133,15 @@ pub async fn get_installed_mod_plugins(
                 .map(|_| plugin.to_owned())
         })
         .collect();
+    installed_plugins.installed_versions.mod_audio = installed_plugins
+        .mod_audio
+        .iter()
+        .filter_map(|plugin| {
+            receipts
+                .remove(plugin)
+                .map(|receipt| (plugin.to_owned(), receipt.into()))
+        })
+        .collect();
 
     Ok(())
 }
@@ -104,3 +156,12 @@ fn plugin_exists(
 
     Ok(exists)
 }
+
+fn get_platform_name() -> &'static str {
+    match Target::current() {
+        Target::MacOS => "macos",
+        Target::Windows => "windows",
+        Target::Linux => "linux",
+        _ => "",
+    }
+}
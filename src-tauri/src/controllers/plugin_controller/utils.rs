@@ -1,5 +1,14 @@
 use super::{plugin_format::PluginFormat, Error};
-use std::path::{Path, PathBuf};
+use crate::operation_controller::CancellationToken;
+use reqwest::StatusCode;
+use sha2::{Digest, Sha256};
+use std::future::Future;
+use std::io::{self, ErrorKind};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
 use tauri::utils::platform::Target;
 
 pub fn get_plugin_path(
@@ -24,6 +33,23 @@ pub fn get_plugin_bundle_name(
     }
 }
 
+pub fn get_staging_path(plugin_path: &Path) -> PathBuf {
+    get_sibling_path(plugin_path, "staging")
+}
+
+pub fn get_backup_path(plugin_path: &Path) -> PathBuf {
+    get_sibling_path(plugin_path, "backup")
+}
+
+pub fn get_receipt_path(plugin_path: &Path) -> PathBuf {
+    get_sibling_path(plugin_path, "receipt.json")
+}
+
+fn get_sibling_path(plugin_path: &Path, suffix: &str) -> PathBuf {
+    let bundle_name = plugin_path.file_name().unwrap_or_default();
+    plugin_path.with_file_name(format!(".{}.{}", bundle_name.to_string_lossy(), suffix))
+}
+
 pub fn get_plugin_folder(plugin_format: &PluginFormat) -> Result<PathBuf, Error> {
     let home_dir = dirs::home_dir();
     let plugin_folder = match (plugin_format, Target::current(), home_dir) {
@@ -42,3 +68,167 @@ pub fn get_plugin_folder(plugin_format: &PluginFormat) -> Result<PathBuf, Error>
 
     Ok(plugin_folder)
 }
+
+pub async fn run_cancellable<T>(
+    token: &CancellationToken,
+    future: impl Future<Output = Result<T, Error>>,
+) -> Result<T, Error> {
+    tokio::select! {
+        _ = token.cancelled() => Err(Error::Cancelled),
+        result = future => result,
+    }
+}
+
+pub fn partition_results<T>(results: Vec<Result<T, Error>>) -> (Vec<T>, Option<Error>) {
+    let mut values = Vec::new();
+    let mut first_error = None;
+    for result in results {
+        match result {
+            Ok(value) => values.push(value),
+            Err(e) => {
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+
+    (values, first_error)
+}
+
+pub fn check_cancelled(token: &CancellationToken) -> Result<(), Error> {
+    if token.is_cancelled() {
+        Err(Error::Cancelled)
+    } else {
+        Ok(())
+    }
+}
+
+// Best effort cleanup that doesn't fail when the path was never created
+pub fn remove_path(path: &Path) -> io::Result<()> {
+    // Don't follow symlinks, only the link itself should be removed
+    let is_dir = fs::symlink_metadata(path).is_ok_and(|metadata| metadata.is_dir());
+    let result = if is_dir {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    };
+
+    match result {
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+// Runs a shell script through AppleScript so macOS asks the user for an administrator password
+pub fn run_with_administrator_privileges(script: &str) -> Result<(), Error> {
+    let apple_script = format!(
+        r#"do shell script "{}" with administrator privileges"#,
+        script
+    );
+    let output = Command::new("osascript")
+        .arg("-e")
+        .arg(apple_script)
+        .output()?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(Error::CreateDirectoryError(
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        ))
+    }
+}
+
+// Single quotes for the shell, with backslashes escaped for the AppleScript string
+pub fn quote_path(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "\\\\");
+    format!("'{}'", path.replace('\'', "'\\\\''").replace('"', "\\\""))
+}
+
+pub fn check_response_status(response: &reqwest::Response) -> Result<(), Error> {
+    let status = response.status();
+    // Github reports an exhausted rate limit as forbidden
+    let is_rate_limited = status == StatusCode::TOO_MANY_REQUESTS
+        || (status == StatusCode::FORBIDDEN
+            && response
+                .headers()
+                .get("x-ratelimit-remaining")
+                .is_some_and(|remaining| remaining == "0"));
+
+    if status.is_success() {
+        Ok(())
+    } else if status == StatusCode::NOT_FOUND {
+        Err(Error::NotFound(response.url().to_string()))
+    } else if is_rate_limited {
+        Err(Error::RateLimited)
+    } else {
+        Err(Error::HttpError(status.as_u16()))
+    }
+}
+
+pub fn is_retryable(error: &Error) -> bool {
+    match error {
+        Error::NetworkError(_) | Error::RateLimited => true,
+        Error::HttpError(status) => *status >= 500 || *status == 408,
+        _ => false,
+    }
+}
+
+#[cfg(unix)]
+pub fn set_file_mode(path: &Path, mode: u32) -> Result<(), io::Error> {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::set_permissions(path, fs::Permissions::from_mode(mode & 0o7777))
+}
+
+#[cfg(not(unix))]
+pub fn set_file_mode(_path: &Path, _mode: u32) -> Result<(), io::Error> {
+    Ok(())
+}
+
+#[cfg(unix)]
+pub fn create_symlink(target: &Path, link: &Path) -> Result<(), io::Error> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+// Windows needs elevated rights for symlinks, copy whatever the link points to instead
+#[cfg(not(unix))]
+pub fn create_symlink(target: &Path, link: &Path) -> Result<(), io::Error> {
+    let source = link.parent().unwrap_or(link).join(target);
+    if !source.is_file() {
+        return Ok(());
+    }
+    fs::copy(source, link).map(|_| ())
+}
+
+pub fn get_file_checksum(path: &Path) -> Result<String, io::Error> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+pub fn is_newer_version(latest: &str, installed: &str) -> bool {
+    let mut latest_parts = parse_version(latest);
+    let mut installed_parts = parse_version(installed);
+    if latest_parts.is_empty() || installed_parts.is_empty() {
+        return latest != installed;
+    }
+
+    // Treat 1.2 and 1.2.0 as the same version
+    let length = latest_parts.len().max(installed_parts.len());
+    latest_parts.resize(length, 0);
+    installed_parts.resize(length, 0);
+
+    latest_parts > installed_parts
+}
+
+fn parse_version(version: &str) -> Vec<u64> {
+    version
+        .split('.')
+        .map_while(|part| {
+            let digits: String = part.chars().take_while(|c| c.is_ascii_digit()).collect();
+            digits.parse().ok()
+        })
+        .collect()
+}
@@ -0,0 +1,104 @@
+#[path = "../services/known_hosts_service.rs"]
+mod known_hosts_service;
+#[path = "../models/mod_device.rs"]
+mod mod_device;
+#[path = "../models/settings.rs"]
+mod settings;
+#[path = "../services/settings_service.rs"]
+mod settings_service;
+use known_hosts_service::KnownHostsService;
+pub use mod_device::{ModDevice, PrivateKey};
+pub use settings::{
+    CacheSettings, GithubSettings, NetworkSettings, Settings, VerificationSettings,
+};
+pub use settings_service::{SettingsError, SettingsService};
+use tauri::Manager;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("{0}")]
+    SettingsError(#[from] SettingsError),
+
+    #[error("{0}")]
+    TauriError(#[from] tauri::Error),
+}
+
+impl serde::Serialize for Error {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::ser::Serializer,
+    {
+        serializer.serialize_str(self.to_string().as_ref())
+    }
+}
+
+#[tauri::command]
+pub async fn get_mod_device(handle: tauri::AppHandle) -> Result<ModDevice, Error> {
+    let settings = load_settings(&handle)?;
+
+    Ok(settings.mod_device)
+}
+
+#[tauri::command]
+pub async fn save_mod_device(device: ModDevice, handle: tauri::AppHandle) -> Result<(), Error> {
+    let settings_service = get_settings_service(&handle)?;
+    let mut settings = settings_service.load()?;
+    settings.mod_device = device;
+    settings_service.save(&settings)?;
+
+    Ok(())
+}
+
+pub fn load_settings(handle: &tauri::AppHandle) -> Result<Settings, Error> {
+    let settings = get_settings_service(handle)?.load()?;
+
+    Ok(settings)
+}
+
+pub fn resolve_mod_device(
+    device: Option<ModDevice>,
+    handle: &tauri::AppHandle,
+) -> Result<ModDevice, Error> {
+    match device {
+        Some(device) => Ok(device),
+        None => Ok(load_settings(handle)?.mod_device),
+    }
+}
+
+pub fn get_pinned_host_key(
+    device: &ModDevice,
+    handle: &tauri::AppHandle,
+) -> Result<Option<String>, Error> {
+    let fingerprint = get_known_hosts_service(handle)?.get(&device.address())?;
+
+    Ok(fingerprint)
+}
+
+pub fn pin_host_key(
+    device: &ModDevice,
+    fingerprint: &str,
+    handle: &tauri::AppHandle,
+) -> Result<(), Error> {
+    get_known_hosts_service(handle)?.insert(&device.address(), fingerprint)?;
+
+    Ok(())
+}
+
+pub fn unpin_host_key(device: &ModDevice, handle: &tauri::AppHandle) -> Result<(), Error> {
+    get_known_hosts_service(handle)?.remove(&device.address())?;
+
+    Ok(())
+}
+
+fn get_known_hosts_service(handle: &tauri::AppHandle) -> Result<KnownHostsService, Error> {
+    let config_folder = handle.path().app_config_dir()?;
+
+    Ok(KnownHostsService::new(config_folder))
+}
+
+fn get_settings_service(handle: &tauri::AppHandle) -> Result<SettingsService, Error> {
+    let config_folder = handle.path().app_config_dir()?;
+
+    Ok(SettingsService::new(config_folder))
+}
@@ -1,12 +1,22 @@
 use tauri::Manager;
 use tauri_plugin_log::{Target, TargetKind};
+#[path = "./controllers/settings_controller.rs"]
+mod settings_controller;
+use settings_controller::{get_mod_device, save_mod_device};
+#[path = "./controllers/operation_controller.rs"]
+mod operation_controller;
+use operation_controller::{cancel_operation, OperationService};
 #[path = "./controllers/mod_plugin_controller.rs"]
 mod mod_plugin_controller;
-use mod_plugin_controller::{create_mod_plugins, delete_mod_plugin, get_mod_plugins};
+use mod_plugin_controller::{
+    create_mod_plugins, delete_mod_plugin, get_app_public_key, get_mod_host_key, get_mod_plugins,
+    install_mod_public_key, reset_mod_host_key,
+};
 #[path = "./controllers/plugin_controller.rs"]
 mod plugin_controller;
 use plugin_controller::{
-    create_plugins, delete_plugins, get_installable_plugins, get_installed_plugins,
+    check_plugin_updates, clear_cache, create_plugins, delete_plugins, get_cache_info,
+    get_installable_plugins, get_installed_plugins, get_plugin_releases, HttpService,
 };
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -33,14 +43,27 @@ pub fn run() {
                 .build(),
         )
         .plugin(tauri_plugin_process::init())
+        .manage(OperationService::default())
+        .manage(HttpService::default())
         .invoke_handler(tauri::generate_handler![
             get_installable_plugins,
             get_installed_plugins,
+            check_plugin_updates,
+            get_plugin_releases,
+            get_cache_info,
+            clear_cache,
             delete_plugins,
             create_plugins,
             get_mod_plugins,
             create_mod_plugins,
-            delete_mod_plugin
+            delete_mod_plugin,
+            get_mod_device,
+            save_mod_device,
+            get_mod_host_key,
+            reset_mod_host_key,
+            get_app_public_key,
+            install_mod_public_key,
+            cancel_operation
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub enum ArchiveFormat {
+    #[default]
+    #[serde(rename = "zip")]
+    Zip,
+    #[serde(rename = "tar.gz")]
+    TarGz,
+    #[serde(rename = "tar.xz")]
+    TarXz,
+}
+
+impl ArchiveFormat {
+    const ALL: [ArchiveFormat; 3] = [Self::Zip, Self::TarGz, Self::TarXz];
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Zip => "zip",
+            Self::TarGz => "tar.gz",
+            Self::TarXz => "tar.xz",
+        }
+    }
+
+    pub fn from_file_name(file_name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|format| file_name.ends_with(&format!(".{}", format.extension())))
+    }
+
+    // Unlike Path::file_stem this strips both parts of a .tar.gz extension
+    pub fn strip_extension(file_name: &str) -> &str {
+        match Self::from_file_name(file_name) {
+            Some(format) => &file_name[..file_name.len() - format.extension().len() - 1],
+            None => file_name,
+        }
+    }
+}
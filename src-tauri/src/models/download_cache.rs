@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CacheEntry {
+    pub sha256: String,
+    pub size: u64,
+    #[serde(rename = "lastUsed")]
+    pub last_used: u64,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct CacheIndex {
+    pub entries: HashMap<String, CacheEntry>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct CacheInfo {
+    pub path: String,
+    pub entries: usize,
+    pub size: u64,
+    #[serde(rename = "maxSize")]
+    pub max_size: u64,
+}
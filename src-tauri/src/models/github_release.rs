@@ -0,0 +1,12 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+pub struct GithubRelease {
+    pub tag_name: String,
+    pub name: Option<String>,
+    pub published_at: Option<String>,
+    #[serde(default)]
+    pub prerelease: bool,
+    #[serde(default)]
+    pub draft: bool,
+}
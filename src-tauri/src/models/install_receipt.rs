@@ -0,0 +1,22 @@
+use super::plugins::PluginVersion;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct InstallReceipt {
+    pub version: String,
+    pub tag: String,
+    pub asset: String,
+    #[serde(rename = "installedAt")]
+    pub installed_at: u64,
+    pub sha256: String,
+}
+
+impl From<InstallReceipt> for PluginVersion {
+    fn from(receipt: InstallReceipt) -> Self {
+        Self {
+            version: receipt.version,
+            tag: Some(receipt.tag),
+            installed_at: Some(receipt.installed_at),
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type")]
+pub enum PrivateKey {
+    #[serde(rename = "file")]
+    File {
+        path: String,
+        passphrase: Option<String>,
+    },
+    #[serde(rename = "app")]
+    App,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct ModDevice {
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    pub password: String,
+    #[serde(rename = "privateKey")]
+    pub private_key: Option<PrivateKey>,
+}
+
+impl Default for ModDevice {
+    fn default() -> Self {
+        Self {
+            host: "192.168.51.1".to_string(),
+            port: 22,
+            username: "root".to_string(),
+            password: "mod".to_string(),
+            private_key: None,
+        }
+    }
+}
+
+impl ModDevice {
+    pub fn address(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+}
@@ -0,0 +1,22 @@
+use serde::Serialize;
+
+#[derive(Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum UpdateStatus {
+    UpToDate,
+    UpdateAvailable,
+    Unknown,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct PluginUpdate {
+    pub name: String,
+    pub format: String,
+    #[serde(rename = "installedVersion")]
+    pub installed_version: Option<String>,
+    #[serde(rename = "latestVersion")]
+    pub latest_version: Option<String>,
+    #[serde(rename = "latestTag")]
+    pub latest_tag: Option<String>,
+    pub status: UpdateStatus,
+}
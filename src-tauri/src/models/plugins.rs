@@ -1,25 +1,82 @@
-use serde::{Deserialize, Serialize};
+use super::archive_format::ArchiveFormat;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+
+const FILE_TYPE_MASK: u32 = 0o170000;
+pub const SYMLINK_TYPE: u32 = 0o120000;
 
 #[derive(Serialize, Deserialize, Default)]
 pub struct ModPlugins {
-    #[serde(rename = "Duo")]
-    pub duo: Vec<String>,
-    #[serde(rename = "DuoX")]
-    pub duo_x: Vec<String>,
-    #[serde(rename = "Dwarf")]
-    pub dwarf: Vec<String>,
+    #[serde(rename = "Duo", deserialize_with = "deserialize_entries")]
+    pub duo: Vec<PluginEntry>,
+    #[serde(rename = "DuoX", deserialize_with = "deserialize_entries")]
+    pub duo_x: Vec<PluginEntry>,
+    #[serde(rename = "Dwarf", deserialize_with = "deserialize_entries")]
+    pub dwarf: Vec<PluginEntry>,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct PluginsConfig {
-    #[serde(rename = "VST3")]
-    pub vst3: Vec<String>,
-    #[serde(rename = "CLAP")]
-    pub clap: Vec<String>,
+    #[serde(rename = "VST3", deserialize_with = "deserialize_entries")]
+    pub vst3: Vec<PluginEntry>,
+    #[serde(rename = "CLAP", deserialize_with = "deserialize_entries")]
+    pub clap: Vec<PluginEntry>,
     #[serde(rename = "MOD Audio")]
     pub mod_audio: ModPlugins,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct PluginEntry {
+    pub name: String,
+    #[serde(rename = "displayName")]
+    pub display_name: Option<String>,
+    pub description: Option<String>,
+    pub category: Option<String>,
+    pub repo: Option<String>,
+    #[serde(rename = "minAppVersion")]
+    pub min_app_version: Option<String>,
+    pub platforms: Option<Vec<String>>,
+    #[serde(rename = "archiveFormat")]
+    pub archive_format: Option<ArchiveFormat>,
+    pub architectures: Option<Vec<String>>,
+    // Releases of signed plugins are rejected when the signature is missing
+    #[serde(default)]
+    pub signed: bool,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub checksums: Checksums,
+}
+
+// Sha256 checksums of release assets, by release tag and asset name
+pub type Checksums = HashMap<String, HashMap<String, String>>;
+
+// Older catalogs only list plugin names
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PluginEntryValue {
+    Name(String),
+    Entry(PluginEntry),
+}
+
+fn deserialize_entries<'de, D>(deserializer: D) -> Result<Vec<PluginEntry>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let values: Vec<PluginEntryValue> = Vec::deserialize(deserializer)?;
+    let entries = values
+        .into_iter()
+        .map(|value| match value {
+            PluginEntryValue::Name(name) => PluginEntry {
+                name,
+                ..Default::default()
+            },
+            PluginEntryValue::Entry(entry) => entry,
+        })
+        .collect();
+
+    Ok(entries)
+}
+
 #[derive(Serialize, Deserialize, Default)]
 pub struct GetPluginsResponse {
     #[serde(rename = "VST3")]
@@ -30,20 +87,246 @@ pub struct GetPluginsResponse {
     pub mod_audio: Vec<String>,
     #[serde(rename = "modIsConnected")]
     pub mod_is_connected: bool,
+    #[serde(rename = "installedVersions", default)]
+    pub installed_versions: InstalledVersions,
+    #[serde(rename = "pluginInfo", default)]
+    pub plugin_info: HashMap<String, PluginEntry>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct InstalledVersions {
+    #[serde(rename = "VST3")]
+    pub vst3: HashMap<String, PluginVersion>,
+    #[serde(rename = "CLAP")]
+    pub clap: HashMap<String, PluginVersion>,
+    #[serde(rename = "MOD Audio")]
+    pub mod_audio: HashMap<String, PluginVersion>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PluginVersion {
+    pub version: String,
+    pub tag: Option<String>,
+    #[serde(rename = "installedAt")]
+    pub installed_at: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize, Default)]
 pub struct SelectedPlugins {
     #[serde(rename = "VST3")]
-    pub vst3: Vec<String>,
+    pub vst3: Vec<SelectedPlugin>,
     #[serde(rename = "CLAP")]
-    pub clap: Vec<String>,
+    pub clap: Vec<SelectedPlugin>,
     #[serde(rename = "MOD Audio")]
-    pub mod_audio: Vec<String>,
+    pub mod_audio: Vec<SelectedPlugin>,
+}
+
+// Either a plain plugin name, which installs the latest release, or a name with a release tag
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum SelectedPlugin {
+    Name(String),
+    Release { name: String, tag: Option<String> },
+}
+
+impl SelectedPlugin {
+    pub fn name(&self) -> &str {
+        match self {
+            SelectedPlugin::Name(name) => name,
+            SelectedPlugin::Release { name, .. } => name,
+        }
+    }
+
+    pub fn tag(&self) -> Option<&str> {
+        match self {
+            SelectedPlugin::Name(_) => None,
+            SelectedPlugin::Release { tag, .. } => tag.as_deref(),
+        }
+    }
+
+    pub fn names(plugins: &[SelectedPlugin]) -> Vec<String> {
+        plugins
+            .iter()
+            .map(|plugin| plugin.name().to_string())
+            .collect()
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct PluginRelease {
+    pub tag: String,
+    pub version: String,
+    pub name: Option<String>,
+    #[serde(rename = "publishedAt")]
+    pub published_at: Option<String>,
+    pub prerelease: bool,
 }
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct ArrayBufferWithPath {
     pub path: String,
     pub buffer: Vec<u8>,
+    #[serde(default)]
+    pub mode: Option<u32>,
+}
+
+impl ArrayBufferWithPath {
+    // The buffer of a symlink holds the path it points to
+    pub fn is_symlink(&self) -> bool {
+        is_symlink_mode(self.mode)
+    }
+}
+
+pub fn is_symlink_mode(mode: Option<u32>) -> bool {
+    mode.is_some_and(|mode| mode & FILE_TYPE_MASK == SYMLINK_TYPE)
+}
+
+// Resolves the target relative to the link without touching the file system,
+// so it also works for archives that haven't been written to disk yet
+pub fn is_link_inside(link_path: &Path, target: &Path, root: &Path) -> bool {
+    let root: Vec<_> = root.components().collect();
+    let mut resolved: Vec<_> = match link_path.parent() {
+        Some(parent) => parent.components().collect(),
+        None => return false,
+    };
+    if !resolved.starts_with(&root) {
+        return false;
+    }
+
+    for component in target.components() {
+        match component {
+            Component::Normal(_) => resolved.push(component),
+            Component::CurDir => {}
+            Component::ParentDir if resolved.len() > root.len() => {
+                resolved.pop();
+            }
+            _ => return false,
+        }
+    }
+
+    true
+}
+
+// is_link_inside only sees one link at a time, an entry stored below another link or a link
+// that walks `..` out of one ("y -> ." and "x -> y/..") can still end up outside the bundle
+pub fn find_path_through_link(entries: &[(PathBuf, Option<PathBuf>)]) -> Option<&Path> {
+    let links: HashSet<&Path> = entries
+        .iter()
+        .filter(|(_, target)| target.is_some())
+        .map(|(path, _)| path.as_path())
+        .collect();
+    let is_below_link = |path: &Path| path.ancestors().any(|ancestor| links.contains(ancestor));
+
+    entries
+        .iter()
+        .find(|(path, target)| {
+            if path.parent().is_some_and(is_below_link) {
+                return true;
+            }
+
+            let mut resolved = path.parent().map(Path::to_path_buf).unwrap_or_default();
+            for component in target.iter().flat_map(|target| target.components()) {
+                match component {
+                    Component::ParentDir if is_below_link(&resolved) => return true,
+                    Component::ParentDir => {
+                        resolved.pop();
+                    }
+                    Component::Normal(part) => resolved.push(part),
+                    _ => {}
+                }
+            }
+
+            false
+        })
+        .map(|(path, _)| path.as_path())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str) -> (PathBuf, Option<PathBuf>) {
+        (PathBuf::from(path), None)
+    }
+
+    fn link(path: &str, target: &str) -> (PathBuf, Option<PathBuf>) {
+        (PathBuf::from(path), Some(PathBuf::from(target)))
+    }
+
+    #[test]
+    fn is_link_inside_accepts_links_within_the_bundle() {
+        let root = Path::new("dm-Test.vst3");
+        for (link_path, target) in [
+            (
+                "dm-Test.vst3/Contents/x86_64-linux/dm-Test.so",
+                "../Resources/dm-Test.so",
+            ),
+            ("dm-Test.vst3/Contents/Current", "A"),
+            ("dm-Test.vst3/Contents/Self", "."),
+            ("dm-Test.vst3/Contents/Root", ".."),
+        ] {
+            assert!(
+                is_link_inside(Path::new(link_path), Path::new(target), root),
+                "{} -> {} was rejected",
+                link_path,
+                target
+            );
+        }
+    }
+
+    #[test]
+    fn is_link_inside_rejects_hostile_targets() {
+        let root = Path::new("dm-Test.vst3");
+        for (link_path, target) in [
+            ("dm-Test.vst3/Contents/passwd", "/etc/passwd"),
+            ("dm-Test.vst3/Contents/up", "../.."),
+            ("dm-Test.vst3/Contents/up", "../../other.vst3"),
+            ("dm-Test.vst3/Contents/up", "a/../../../.ssh"),
+            ("dm-Test.vst3/up", ".."),
+            ("other.vst3/link", "."),
+            ("link", "dm-Test.vst3"),
+        ] {
+            assert!(
+                !is_link_inside(Path::new(link_path), Path::new(target), root),
+                "{} -> {} was accepted",
+                link_path,
+                target
+            );
+        }
+    }
+
+    #[test]
+    fn find_path_through_link_accepts_regular_bundles() {
+        let entries = [
+            file("dm-Test.vst3/Contents/A/dm-Test"),
+            link("dm-Test.vst3/Contents/Current", "A"),
+            link("dm-Test.vst3/Contents/dm-Test", "Current/dm-Test"),
+            link("dm-Test.vst3/Contents/lib/dm-Test.so", "../A/dm-Test"),
+        ];
+
+        assert_eq!(find_path_through_link(&entries), None);
+    }
+
+    #[test]
+    fn find_path_through_link_rejects_link_chains() {
+        let entries = [link("dm-Test.lv2/y", "."), link("dm-Test.lv2/x", "y/..")];
+
+        assert_eq!(
+            find_path_through_link(&entries),
+            Some(Path::new("dm-Test.lv2/x"))
+        );
+    }
+
+    #[test]
+    fn find_path_through_link_rejects_entries_below_a_link() {
+        let entries = [
+            link("dm-Test.lv2/lib", "."),
+            file("dm-Test.lv2/lib/dm-Test.so"),
+        ];
+
+        assert_eq!(
+            find_path_through_link(&entries),
+            Some(Path::new("dm-Test.lv2/lib/dm-Test.so"))
+        );
+    }
 }
@@ -0,0 +1,22 @@
+use serde::Serialize;
+
+#[derive(Serialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum ProgressPhase {
+    Downloading,
+    Extracting,
+    Copying,
+    Uploading,
+    Done,
+}
+
+#[derive(Serialize, Clone)]
+pub struct PluginProgress {
+    pub plugin: String,
+    pub format: String,
+    pub phase: ProgressPhase,
+    #[serde(rename = "bytesDone")]
+    pub bytes_done: u64,
+    #[serde(rename = "bytesTotal")]
+    pub bytes_total: Option<u64>,
+}
@@ -0,0 +1,116 @@
+use super::mod_device::ModDevice;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct Settings {
+    #[serde(rename = "modDevice")]
+    pub mod_device: ModDevice,
+    pub github: GithubSettings,
+    pub catalog: CatalogSettings,
+    pub verification: VerificationSettings,
+    pub cache: CacheSettings,
+    pub network: NetworkSettings,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct GithubSettings {
+    #[serde(rename = "apiUrl")]
+    pub api_url: String,
+    #[serde(rename = "downloadUrl")]
+    pub download_url: String,
+    pub owner: String,
+}
+
+impl Default for GithubSettings {
+    fn default() -> Self {
+        Self {
+            api_url: "https://api.github.com".to_string(),
+            download_url: "https://github.com".to_string(),
+            owner: "davemollen".to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct CatalogSettings {
+    pub url: String,
+}
+
+impl Default for CatalogSettings {
+    fn default() -> Self {
+        Self {
+            url: "https://raw.githubusercontent.com/davemollen/dm-plugin-manager/main/src-tauri/resources/dm-plugins.json".to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct VerificationSettings {
+    #[serde(rename = "requireChecksums")]
+    pub require_checksums: bool,
+    #[serde(rename = "requireSignatures")]
+    pub require_signatures: bool,
+    #[serde(rename = "trustedKeys")]
+    pub trusted_keys: Vec<String>,
+}
+
+impl Default for VerificationSettings {
+    fn default() -> Self {
+        Self {
+            require_checksums: false,
+            require_signatures: false,
+            // The same minisign key that signs the app updates
+            trusted_keys: vec![
+                "RWTEaWsvWTNx0FOAtkxC7MVg0yzNLjPMjZWhqaBdPg/q2QY9vBDcruI9".to_string()
+            ],
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct CacheSettings {
+    #[serde(rename = "maxSizeMb")]
+    pub max_size_mb: u64,
+}
+
+impl Default for CacheSettings {
+    fn default() -> Self {
+        Self { max_size_mb: 1024 }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct NetworkSettings {
+    #[serde(rename = "connectTimeoutSecs")]
+    pub connect_timeout_secs: u64,
+    #[serde(rename = "readTimeoutSecs")]
+    pub read_timeout_secs: u64,
+    pub retries: u32,
+    #[serde(rename = "maxConcurrentDownloads")]
+    pub max_concurrent_downloads: usize,
+    pub proxy: Option<String>,
+    #[serde(rename = "noProxy")]
+    pub no_proxy: Vec<String>,
+    #[serde(rename = "caCertificates")]
+    pub ca_certificates: Vec<String>,
+}
+
+impl Default for NetworkSettings {
+    fn default() -> Self {
+        Self {
+            connect_timeout_secs: 10,
+            read_timeout_secs: 30,
+            retries: 3,
+            max_concurrent_downloads: 3,
+            proxy: None,
+            no_proxy: Vec::new(),
+            ca_certificates: Vec::new(),
+        }
+    }
+}
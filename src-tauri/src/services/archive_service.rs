@@ -0,0 +1,436 @@
+use super::archive_format::ArchiveFormat;
+use super::plugins::{find_path_through_link, is_link_inside};
+use super::tar_service::TarService;
+use super::zip_service::ZipService;
+use crate::mod_plugin_controller::ArrayBufferWithPath;
+use flate2::read::GzDecoder;
+use std::fs::{self, File};
+use std::io::{self, BufReader, ErrorKind};
+use std::path::{Component, Path, PathBuf};
+use xz2::read::XzDecoder;
+
+pub struct ArchiveService;
+
+impl ArchiveService {
+    pub fn list_files(file_path: &PathBuf) -> Result<Vec<PathBuf>, io::Error> {
+        match Self::get_format(file_path)? {
+            ArchiveFormat::Zip => ZipService::list_files(file_path),
+            ArchiveFormat::TarGz => TarService::list_files(GzDecoder::new(Self::open(file_path)?)),
+            ArchiveFormat::TarXz => TarService::list_files(XzDecoder::new(Self::open(file_path)?)),
+        }
+    }
+
+    // Only the entries below prefix are written, with prefix itself mapped onto destination
+    pub fn extract_prefix(
+        file_path: &PathBuf,
+        prefix: &Path,
+        destination: &Path,
+    ) -> Result<(), io::Error> {
+        match Self::get_format(file_path)? {
+            ArchiveFormat::Zip => ZipService::unzip_prefix(file_path, prefix, destination),
+            ArchiveFormat::TarGz => TarService::unpack_prefix(
+                GzDecoder::new(Self::open(file_path)?),
+                prefix,
+                destination,
+            ),
+            ArchiveFormat::TarXz => TarService::unpack_prefix(
+                XzDecoder::new(Self::open(file_path)?),
+                prefix,
+                destination,
+            ),
+        }
+    }
+
+    pub fn read_files(
+        file_path: &PathBuf,
+        starts_with: &PathBuf,
+    ) -> Result<Vec<ArrayBufferWithPath>, io::Error> {
+        let files = match Self::get_format(file_path)? {
+            ArchiveFormat::Zip => ZipService::unzip_to_u8(file_path, starts_with)?,
+            ArchiveFormat::TarGz => {
+                TarService::read_files(GzDecoder::new(Self::open(file_path)?), starts_with)?
+            }
+            ArchiveFormat::TarXz => {
+                TarService::read_files(XzDecoder::new(Self::open(file_path)?), starts_with)?
+            }
+        };
+
+        // Nothing is on disk yet, so link chains are checked on the paths alone
+        let entries: Vec<_> = files
+            .iter()
+            .map(|file| {
+                let target = file
+                    .is_symlink()
+                    .then(|| PathBuf::from(String::from_utf8_lossy(&file.buffer).as_ref()));
+                (PathBuf::from(&file.path), target)
+            })
+            .collect();
+        if let Some(path) = find_path_through_link(&entries) {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("{} passes through a symlink", path.display()),
+            ));
+        }
+
+        Ok(files)
+    }
+
+    // Archives contain a folder named after the archive
+    pub fn get_root_folder(file_path: &PathBuf) -> Result<PathBuf, io::Error> {
+        let file_name = match file_path.file_name() {
+            Some(file_name) => file_name.to_string_lossy(),
+            None => return Err(Self::unsupported(file_path)),
+        };
+
+        Ok(file_path.with_file_name(ArchiveFormat::strip_extension(&file_name)))
+    }
+
+    // Same rules as zip's enclosed_name, entries can't escape the extraction folder
+    pub fn get_enclosed_path(path: &Path) -> Option<PathBuf> {
+        let mut enclosed_path = PathBuf::new();
+        for component in path.components() {
+            match component {
+                Component::Normal(part) => enclosed_path.push(part),
+                Component::CurDir => {}
+                _ => return None,
+            }
+        }
+
+        if enclosed_path.as_os_str().is_empty() {
+            None
+        } else {
+            Some(enclosed_path)
+        }
+    }
+
+    pub fn get_output_path(path: &Path, prefix: &Path, destination: &Path) -> Option<PathBuf> {
+        let relative_path = path.strip_prefix(prefix).ok()?;
+        // Joining an empty path would add a trailing separator
+        if relative_path.as_os_str().is_empty() {
+            Some(destination.to_path_buf())
+        } else {
+            Some(destination.join(relative_path))
+        }
+    }
+
+    pub fn get_root(path: &Path) -> PathBuf {
+        path.components().take(1).collect()
+    }
+
+    pub fn check_link(path: &Path, target: &Path, root: &Path) -> Result<(), io::Error> {
+        if is_link_inside(path, target, root) {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("{} links outside of the plugin bundle", path.display()),
+            ))
+        }
+    }
+
+    // The link check above is lexical, so nothing is written through a symlink an earlier entry created
+    pub fn check_output_path(output_path: &Path, destination: &Path) -> Result<(), io::Error> {
+        let relative_path = output_path
+            .strip_prefix(destination)
+            .unwrap_or(Path::new(""));
+        let mut path = destination.to_path_buf();
+        for component in relative_path.components() {
+            path.push(component);
+            if fs::symlink_metadata(&path).is_ok_and(|metadata| metadata.is_symlink()) {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("{} is written through a symlink", output_path.display()),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    // A later link can change where an earlier one resolves to, so links are resolved once all are in place
+    pub fn check_links(links: &[PathBuf], destination: &Path) -> Result<(), io::Error> {
+        if links.is_empty() {
+            return Ok(());
+        }
+
+        let destination = fs::canonicalize(destination)?;
+        for link in links {
+            // Targets without symlink support copy the target instead
+            if !fs::symlink_metadata(link).is_ok_and(|metadata| metadata.is_symlink()) {
+                continue;
+            }
+            let is_inside =
+                fs::canonicalize(link).is_ok_and(|target| target.starts_with(&destination));
+            if !is_inside {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("{} links outside of the plugin bundle", link.display()),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_format(file_path: &Path) -> Result<ArchiveFormat, io::Error> {
+        file_path
+            .file_name()
+            .and_then(|file_name| ArchiveFormat::from_file_name(&file_name.to_string_lossy()))
+            .ok_or_else(|| Self::unsupported(file_path))
+    }
+
+    fn open(file_path: &Path) -> Result<BufReader<File>, io::Error> {
+        Ok(BufReader::new(File::open(file_path)?))
+    }
+
+    fn unsupported(file_path: &Path) -> io::Error {
+        io::Error::new(
+            ErrorKind::Unsupported,
+            format!("{} is not a supported archive", file_path.display()),
+        )
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+    use std::os::unix::fs::symlink;
+    use tar::{Builder, EntryType, Header};
+    use zip::write::SimpleFileOptions;
+    use zip::ZipWriter;
+
+    // Entries are a path and, for symlinks, the target
+    type TestEntry<'a> = (&'a str, Option<&'a str>);
+
+    const PREFIX: &str = "dm-Test/dm-Test.vst3";
+
+    fn get_test_folder(name: &str) -> PathBuf {
+        let folder = std::env::temp_dir().join(format!(
+            "dm-plugin-manager-archive-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&folder);
+        fs::create_dir_all(&folder).unwrap();
+        folder
+    }
+
+    fn write_tar_gz(path: &Path, entries: &[TestEntry]) {
+        let encoder = GzEncoder::new(File::create(path).unwrap(), Compression::default());
+        let mut builder = Builder::new(encoder);
+        for (name, target) in entries {
+            let mut header = Header::new_gnu();
+            match target {
+                Some(target) => {
+                    header.set_entry_type(EntryType::Symlink);
+                    header.set_size(0);
+                    header.set_mode(0o777);
+                    builder.append_link(&mut header, name, target).unwrap();
+                }
+                None => {
+                    header.set_size(4);
+                    header.set_mode(0o644);
+                    header.set_cksum();
+                    builder
+                        .append_data(&mut header, name, &b"test"[..])
+                        .unwrap();
+                }
+            }
+        }
+        builder.into_inner().unwrap().finish().unwrap();
+    }
+
+    fn write_zip(path: &Path, entries: &[TestEntry]) {
+        let mut writer = ZipWriter::new(File::create(path).unwrap());
+        let options = SimpleFileOptions::default();
+        for (name, target) in entries {
+            match target {
+                Some(target) => writer.add_symlink(*name, *target, options).unwrap(),
+                None => {
+                    writer.start_file(*name, options).unwrap();
+                    writer.write_all(b"test").unwrap();
+                }
+            }
+        }
+        writer.finish().unwrap();
+    }
+
+    // Writes the entries as both a tar.gz and a zip archive
+    fn write_archives(folder: &Path, entries: &[TestEntry]) -> [PathBuf; 2] {
+        let tar_path = folder.join("dm-Test.tar.gz");
+        let zip_path = folder.join("dm-Test.zip");
+        write_tar_gz(&tar_path, entries);
+        write_zip(&zip_path, entries);
+        [tar_path, zip_path]
+    }
+
+    fn extract(file_path: &PathBuf, folder: &Path) -> Result<PathBuf, io::Error> {
+        let destination = folder.join("output").join("dm-Test.vst3");
+        let _ = fs::remove_dir_all(folder.join("output"));
+        fs::create_dir_all(&destination)?;
+        ArchiveService::extract_prefix(file_path, Path::new(PREFIX), &destination)?;
+        Ok(destination)
+    }
+
+    fn assert_invalid_data<T>(result: Result<T, io::Error>) {
+        match result {
+            Err(error) => assert_eq!(error.kind(), ErrorKind::InvalidData, "{}", error),
+            Ok(_) => panic!("the archive was accepted"),
+        }
+    }
+
+    #[test]
+    fn extract_prefix_keeps_links_within_the_bundle() {
+        let folder = get_test_folder("extract-links");
+        let entries = [
+            ("dm-Test/dm-Test.vst3/Contents/A/dm-Test", None),
+            ("dm-Test/dm-Test.vst3/Contents/Current", Some("A")),
+            (
+                "dm-Test/dm-Test.vst3/Contents/dm-Test",
+                Some("Current/dm-Test"),
+            ),
+        ];
+
+        for archive in write_archives(&folder, &entries) {
+            let destination = extract(&archive, &folder).unwrap();
+            let link = destination.join("Contents/dm-Test");
+            assert!(fs::symlink_metadata(&link).unwrap().is_symlink());
+            assert_eq!(fs::read(link).unwrap(), b"test");
+        }
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn extract_prefix_rejects_hostile_link_targets() {
+        let folder = get_test_folder("extract-targets");
+        for target in ["/etc/passwd", "../outside", "Contents/../../outside"] {
+            let entries = [("dm-Test/dm-Test.vst3/link", Some(target))];
+            for archive in write_archives(&folder, &entries) {
+                assert_invalid_data(extract(&archive, &folder));
+            }
+        }
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn extract_prefix_rejects_link_chains_resolving_outside() {
+        let folder = get_test_folder("extract-chain");
+        // Each link stays inside on its own, x only escapes once y is a symlink
+        let entries = [
+            ("dm-Test/dm-Test.vst3/y", Some(".")),
+            ("dm-Test/dm-Test.vst3/x", Some("y/..")),
+        ];
+
+        for archive in write_archives(&folder, &entries) {
+            assert_invalid_data(extract(&archive, &folder));
+        }
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn extract_prefix_does_not_write_through_links() {
+        let folder = get_test_folder("extract-through");
+        let entries = [
+            ("dm-Test/dm-Test.vst3/Contents/lib", Some(".")),
+            ("dm-Test/dm-Test.vst3/Contents/lib/evil", None),
+        ];
+
+        for archive in write_archives(&folder, &entries) {
+            assert_invalid_data(extract(&archive, &folder));
+            assert!(!folder.join("output/dm-Test.vst3/Contents/evil").exists());
+        }
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn read_files_keeps_links_within_the_bundle() {
+        let folder = get_test_folder("read-links");
+        let entries = [
+            ("dm-Test/dm-Test.lv2/dm-Test.so", None),
+            ("dm-Test/dm-Test.lv2/lib/dm-Test.so", Some("../dm-Test.so")),
+        ];
+
+        for archive in write_archives(&folder, &entries) {
+            let files = ArchiveService::read_files(&archive, &PathBuf::from("dm-Test/dm-Test.lv2"))
+                .unwrap();
+            let link = files.iter().find(|file| file.is_symlink()).unwrap();
+            assert_eq!(link.path, "dm-Test.lv2/lib/dm-Test.so");
+            assert_eq!(link.buffer, b"../dm-Test.so");
+        }
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn read_files_rejects_hostile_links() {
+        let folder = get_test_folder("read-hostile");
+        let hostile_entries: [&[TestEntry]; 4] = [
+            &[("dm-Test/dm-Test.lv2/link", Some("/etc/passwd"))],
+            &[("dm-Test/dm-Test.lv2/lib/link", Some("../../outside"))],
+            &[
+                ("dm-Test/dm-Test.lv2/y", Some(".")),
+                ("dm-Test/dm-Test.lv2/x", Some("y/..")),
+            ],
+            &[
+                ("dm-Test/dm-Test.lv2/lib", Some(".")),
+                ("dm-Test/dm-Test.lv2/lib/dm-Test.so", None),
+            ],
+        ];
+
+        for entries in hostile_entries {
+            for archive in write_archives(&folder, entries) {
+                assert_invalid_data(ArchiveService::read_files(
+                    &archive,
+                    &PathBuf::from("dm-Test/dm-Test.lv2"),
+                ));
+            }
+        }
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn check_output_path_rejects_paths_through_links() {
+        let destination = get_test_folder("output-path");
+        fs::create_dir(destination.join("Contents")).unwrap();
+        symlink("/tmp", destination.join("Contents/lib")).unwrap();
+
+        assert!(ArchiveService::check_output_path(
+            &destination.join("Contents/file"),
+            &destination
+        )
+        .is_ok());
+        assert_invalid_data(ArchiveService::check_output_path(
+            &destination.join("Contents/lib"),
+            &destination,
+        ));
+        assert_invalid_data(ArchiveService::check_output_path(
+            &destination.join("Contents/lib/evil"),
+            &destination,
+        ));
+        fs::remove_dir_all(destination).unwrap();
+    }
+
+    #[test]
+    fn check_links_resolves_links_on_disk() {
+        let destination = get_test_folder("check-links");
+        fs::write(destination.join("file"), b"test").unwrap();
+        symlink("file", destination.join("inside")).unwrap();
+        symlink("/etc", destination.join("absolute")).unwrap();
+        symlink(".", destination.join("y")).unwrap();
+        symlink("y/..", destination.join("x")).unwrap();
+
+        assert!(ArchiveService::check_links(&[], &destination).is_ok());
+        assert!(ArchiveService::check_links(&[destination.join("inside")], &destination).is_ok());
+        assert_invalid_data(ArchiveService::check_links(
+            &[destination.join("absolute")],
+            &destination,
+        ));
+        assert_invalid_data(ArchiveService::check_links(
+            &[destination.join("y"), destination.join("x")],
+            &destination,
+        ));
+        fs::remove_dir_all(destination).unwrap();
+    }
+}
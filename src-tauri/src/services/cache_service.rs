@@ -0,0 +1,190 @@
+use super::download_cache::{CacheEntry, CacheIndex, CacheInfo};
+use super::utils::{get_file_checksum, remove_path};
+use super::Error;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Downloaded archives are stored by their sha256, the index maps plugin, release and asset to them
+pub struct CacheService {
+    folder: PathBuf,
+    max_size: u64,
+    index_lock: Mutex<()>,
+}
+
+impl CacheService {
+    const INDEX_FILE_NAME: &'static str = "index.json";
+    const BLOB_FOLDER: &'static str = "blobs";
+
+    pub fn new(folder: PathBuf, max_size: u64) -> Self {
+        Self {
+            folder,
+            max_size,
+            index_lock: Mutex::new(()),
+        }
+    }
+
+    pub fn get_key(plugin_name: &str, tag: &str, asset: &str) -> String {
+        format!("{}/{}/{}", plugin_name, tag, asset)
+    }
+
+    pub fn get(&self, key: &str, destination: &Path) -> Result<Option<CacheEntry>, Error> {
+        if self.max_size == 0 {
+            return Ok(None);
+        }
+        let _guard = self.index_lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut index = self.read_index();
+        let entry = match index.entries.get_mut(key) {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+
+        // Drop entries whose file went missing or got corrupted
+        let blob_path = self.get_blob_path(&entry.sha256);
+        if get_file_checksum(&blob_path).ok().as_deref() != Some(entry.sha256.as_str()) {
+            index.entries.remove(key);
+            self.remove_unused_blobs(&index)?;
+            self.write_index(&index)?;
+            return Ok(None);
+        }
+
+        fs::copy(&blob_path, destination)?;
+        entry.last_used = now();
+        let entry = entry.clone();
+        self.write_index(&index)?;
+
+        Ok(Some(entry))
+    }
+
+    pub fn insert(&self, key: &str, source: &Path, sha256: &str) -> Result<(), Error> {
+        let size = fs::metadata(source)?.len();
+        if self.max_size == 0 || size > self.max_size {
+            return Ok(());
+        }
+        let _guard = self.index_lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut index = self.read_index();
+
+        let blob_path = self.get_blob_path(sha256);
+        if !blob_path.exists() {
+            fs::create_dir_all(self.folder.join(Self::BLOB_FOLDER))?;
+            let temp_path = blob_path.with_extension("tmp");
+            fs::copy(source, &temp_path)?;
+            fs::rename(&temp_path, &blob_path)?;
+        }
+        index.entries.insert(
+            key.to_string(),
+            CacheEntry {
+                sha256: sha256.to_string(),
+                size,
+                last_used: now(),
+            },
+        );
+        self.evict(&mut index);
+        self.remove_unused_blobs(&index)?;
+        self.write_index(&index)?;
+
+        Ok(())
+    }
+
+    pub fn get_info(&self) -> CacheInfo {
+        let _guard = self.index_lock.lock().unwrap_or_else(|e| e.into_inner());
+        let index = self.read_index();
+
+        CacheInfo {
+            path: self.folder.to_string_lossy().to_string(),
+            entries: index.entries.len(),
+            size: get_size(&index),
+            max_size: self.max_size,
+        }
+    }
+
+    pub fn clear(&self) -> Result<(), Error> {
+        let _guard = self.index_lock.lock().unwrap_or_else(|e| e.into_inner());
+        remove_path(&self.folder)?;
+
+        Ok(())
+    }
+
+    // Remove the least recently used entries until the cache fits its size limit
+    fn evict(&self, index: &mut CacheIndex) {
+        let mut entries: Vec<(String, u64)> = index
+            .entries
+            .iter()
+            .map(|(key, entry)| (key.clone(), entry.last_used))
+            .collect();
+        entries.sort_by_key(|(_, last_used)| *last_used);
+
+        for (key, _) in entries {
+            if get_size(index) <= self.max_size {
+                break;
+            }
+            index.entries.remove(&key);
+        }
+    }
+
+    fn remove_unused_blobs(&self, index: &CacheIndex) -> Result<(), Error> {
+        let blob_folder = self.folder.join(Self::BLOB_FOLDER);
+        let blobs = match fs::read_dir(&blob_folder) {
+            Ok(blobs) => blobs,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+
+        for blob in blobs {
+            let blob = blob?;
+            let file_name = blob.file_name().to_string_lossy().to_string();
+            let is_used = index
+                .entries
+                .values()
+                .any(|entry| entry.sha256 == file_name);
+            if !is_used {
+                remove_path(&blob.path())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_index(&self) -> CacheIndex {
+        File::open(self.folder.join(Self::INDEX_FILE_NAME))
+            .ok()
+            .and_then(|file| serde_json::from_reader(file).ok())
+            .unwrap_or_default()
+    }
+
+    fn write_index(&self, index: &CacheIndex) -> Result<(), Error> {
+        fs::create_dir_all(&self.folder)?;
+        let index_path = self.folder.join(Self::INDEX_FILE_NAME);
+        let temp_path = index_path.with_extension("json.tmp");
+        fs::write(&temp_path, serde_json::to_vec_pretty(index)?)?;
+        fs::rename(&temp_path, &index_path)?;
+
+        Ok(())
+    }
+
+    fn get_blob_path(&self, sha256: &str) -> PathBuf {
+        self.folder.join(Self::BLOB_FOLDER).join(sha256)
+    }
+}
+
+fn get_size(index: &CacheIndex) -> u64 {
+    // Entries that share an archive only count once
+    let mut blobs: Vec<(&String, u64)> = index
+        .entries
+        .values()
+        .map(|entry| (&entry.sha256, entry.size))
+        .collect();
+    blobs.sort();
+    blobs.dedup();
+
+    blobs.iter().map(|(_, size)| size).sum()
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
@@ -0,0 +1,155 @@
+use super::catalog_cache::CatalogCache;
+use super::plugins::PluginsConfig;
+use super::Error;
+use reqwest::header::{
+    HeaderMap, HeaderName, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+};
+use reqwest::StatusCode;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub struct CatalogService {
+    client: reqwest::Client,
+    url: String,
+    catalog_path: PathBuf,
+    cache_path: PathBuf,
+}
+
+impl CatalogService {
+    const FILE_NAME: &'static str = "dm-plugins.json";
+    const CACHE_FILE_NAME: &'static str = "dm-plugins.cache.json";
+
+    pub fn new(cache_folder: PathBuf, url: String, client: reqwest::Client) -> Self {
+        Self {
+            client,
+            url,
+            catalog_path: cache_folder.join(Self::FILE_NAME),
+            cache_path: cache_folder.join(Self::CACHE_FILE_NAME),
+        }
+    }
+
+    pub async fn load(&self, bundled_catalog_path: &Path) -> Result<PluginsConfig, Error> {
+        // When offline or when the remote catalog is invalid, use the last known good copy
+        if let Ok(config) = self.fetch().await {
+            return Ok(config);
+        }
+        if let Ok(config) = Self::read(&self.catalog_path) {
+            return Ok(config);
+        }
+
+        Self::read(bundled_catalog_path)
+    }
+
+    async fn fetch(&self) -> Result<PluginsConfig, Error> {
+        if self.url.is_empty() {
+            return Err(Error::InvalidCatalog(
+                "no catalog url configured".to_string(),
+            ));
+        }
+
+        let mut request = self.client.get(&self.url).timeout(FETCH_TIMEOUT);
+        if let Some(cache) = self.read_cache() {
+            if let Some(etag) = cache.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = cache.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+
+        let response = request.send().await?;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            return Self::read(&self.catalog_path);
+        }
+        let response = response.error_for_status()?;
+        let cache = CatalogCache {
+            etag: get_header(response.headers(), ETAG),
+            last_modified: get_header(response.headers(), LAST_MODIFIED),
+        };
+        let body = response.bytes().await?;
+        let config = Self::parse(&body)?;
+        // The catalog is still usable when it can't be cached
+        let _ = self.write_cache(&body, &cache);
+
+        Ok(config)
+    }
+
+    fn read(path: &Path) -> Result<PluginsConfig, Error> {
+        let contents = fs::read(path)?;
+
+        Self::parse(&contents)
+    }
+
+    fn parse(contents: &[u8]) -> Result<PluginsConfig, Error> {
+        let config: PluginsConfig = serde_json::from_slice(contents)?;
+        validate(&config)?;
+
+        Ok(config)
+    }
+
+    fn read_cache(&self) -> Option<CatalogCache> {
+        if !self.catalog_path.exists() {
+            return None;
+        }
+        let contents = fs::read(&self.cache_path).ok()?;
+
+        serde_json::from_slice(&contents).ok()
+    }
+
+    fn write_cache(&self, contents: &[u8], cache: &CatalogCache) -> Result<(), Error> {
+        if let Some(folder) = self.catalog_path.parent() {
+            fs::create_dir_all(folder)?;
+        }
+
+        let temp_file_path = self.catalog_path.with_extension("json.tmp");
+        fs::write(&temp_file_path, contents)?;
+        fs::rename(&temp_file_path, &self.catalog_path)?;
+        fs::write(&self.cache_path, serde_json::to_vec_pretty(cache)?)?;
+
+        Ok(())
+    }
+}
+
+fn get_header(headers: &HeaderMap, name: HeaderName) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string())
+}
+
+fn validate(config: &PluginsConfig) -> Result<(), Error> {
+    let mut plugin_names = config
+        .vst3
+        .iter()
+        .chain(config.clap.iter())
+        .chain(config.mod_audio.duo.iter())
+        .chain(config.mod_audio.duo_x.iter())
+        .chain(config.mod_audio.dwarf.iter())
+        .map(|entry| entry.name.as_str())
+        .peekable();
+    if plugin_names.peek().is_none() {
+        return Err(Error::InvalidCatalog(
+            "it doesn't contain any plugins".to_string(),
+        ));
+    }
+
+    // Plugin names end up in file paths and urls
+    match plugin_names.find(|name| !is_valid_plugin_name(name)) {
+        Some(name) => Err(Error::InvalidCatalog(format!(
+            "invalid plugin name {}",
+            name
+        ))),
+        None => Ok(()),
+    }
+}
+
+fn is_valid_plugin_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+}
@@ -0,0 +1,370 @@
+use super::archive_format::ArchiveFormat;
+use super::cache_service::CacheService;
+use super::github_service::GithubService;
+use super::plugins::{Checksums, SelectedPlugin};
+use super::progress::ProgressPhase;
+use super::progress_service::ProgressService;
+use super::signature_service::SignatureService;
+use super::utils::{
+    check_response_status, get_file_checksum, is_retryable, remove_path, run_cancellable,
+};
+use super::Error;
+use crate::operation_controller::CancellationToken;
+use crate::settings_controller::{NetworkSettings, VerificationSettings};
+use reqwest::{
+    header::{CONTENT_RANGE, ETAG, IF_RANGE, LAST_MODIFIED, RANGE},
+    StatusCode,
+};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::Semaphore;
+
+const DEFAULT_ARCHITECTURE: &str = "x86_64";
+const PROGRESS_INTERVAL: u64 = 256 * 1024;
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+pub struct DownloadedFile {
+    pub path: PathBuf,
+    pub tag: String,
+    pub asset: String,
+    pub sha256: String,
+}
+
+pub struct DownloadService {
+    github_service: GithubService,
+    signature_service: SignatureService,
+    cache_service: CacheService,
+    verification: VerificationSettings,
+    network: NetworkSettings,
+    checksums: HashMap<String, Checksums>,
+    archive_formats: HashMap<String, ArchiveFormat>,
+    architectures: HashMap<String, Vec<String>>,
+    signed_plugins: HashSet<String>,
+    download_permits: Semaphore,
+}
+
+impl DownloadService {
+    pub fn new(
+        github_service: GithubService,
+        cache_service: CacheService,
+        verification: VerificationSettings,
+        network: NetworkSettings,
+        checksums: HashMap<String, Checksums>,
+        archive_formats: HashMap<String, ArchiveFormat>,
+        architectures: HashMap<String, Vec<String>>,
+        signed_plugins: HashSet<String>,
+    ) -> Self {
+        Self {
+            github_service,
+            signature_service: SignatureService::new(&verification.trusted_keys),
+            cache_service,
+            download_permits: Semaphore::new(network.max_concurrent_downloads.max(1)),
+            verification,
+            network,
+            checksums,
+            archive_formats,
+            architectures,
+            signed_plugins,
+        }
+    }
+
+    // Plugins publish zip files unless the catalog says otherwise
+    pub fn get_archive_format(&self, plugin_name: &str) -> ArchiveFormat {
+        self.archive_formats
+            .get(plugin_name)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn supports_architecture(&self, plugin_name: &str, architecture: &str) -> bool {
+        Self::is_supported_architecture(self.architectures.get(plugin_name), architecture)
+    }
+
+    // Catalogs that predate arm builds only had x86_64 Linux assets
+    pub fn is_supported_architecture(
+        architectures: Option<&Vec<String>>,
+        architecture: &str,
+    ) -> bool {
+        match architectures {
+            Some(architectures) => architectures.iter().any(|arch| arch == architecture),
+            None => architecture == DEFAULT_ARCHITECTURE,
+        }
+    }
+
+    pub async fn download(
+        &self,
+        plugin: &SelectedPlugin,
+        asset: &str,
+        progress: &ProgressService,
+        token: &CancellationToken,
+    ) -> Result<DownloadedFile, Error> {
+        let plugin_name = plugin.name();
+        let tag = match plugin.tag() {
+            Some(tag) => GithubService::validate_tag(tag)?.to_string(),
+            None => {
+                run_cancellable(
+                    token,
+                    self.github_service.get_latest_release_tag(plugin_name),
+                )
+                .await?
+            }
+        };
+        let file_path = std::env::temp_dir().join(asset);
+        let cache_key = CacheService::get_key(plugin_name, &tag, asset);
+        if let Some(entry) = self.cache_service.get(&cache_key, &file_path)? {
+            progress.report(ProgressPhase::Downloading, entry.size, Some(entry.size));
+            return Ok(DownloadedFile {
+                path: file_path,
+                tag,
+                asset: asset.to_string(),
+                sha256: entry.sha256,
+            });
+        }
+
+        let url = self
+            .github_service
+            .get_download_url(plugin_name, &tag, asset);
+        // Part files are kept per release, so a leftover of another tag is never resumed
+        let part_path = std::env::temp_dir().join(format!("{}-{}.part", tag, asset));
+        // Archives are streamed to disk, the permit only bounds how many are in flight at once
+        let permit = run_cancellable(token, async {
+            self.download_permits
+                .acquire()
+                .await
+                .map_err(|_| Error::Cancelled)
+        })
+        .await?;
+        self.fetch(&url, &part_path, progress, token).await?;
+        drop(permit);
+        fs::rename(&part_path, &file_path)?;
+        remove_path(&get_validator_path(&part_path))?;
+
+        let result = async {
+            let sha256 = get_file_checksum(&file_path)?;
+            self.verify_checksum(plugin_name, &tag, asset, &sha256, token)
+                .await?;
+            self.verify_signature(&file_path, plugin_name, &tag, asset, token)
+                .await?;
+            Ok::<String, Error>(sha256)
+        }
+        .await;
+        match result {
+            Ok(sha256) => {
+                // Only verified archives end up in the cache, a cache failure shouldn't fail the install
+                let _ = self.cache_service.insert(&cache_key, &file_path, &sha256);
+                Ok(DownloadedFile {
+                    path: file_path,
+                    tag,
+                    asset: asset.to_string(),
+                    sha256,
+                })
+            }
+            Err(e) => {
+                remove_path(&file_path)?;
+                Err(e)
+            }
+        }
+    }
+
+    // An interrupted download is kept as a part file and resumed on the next attempt
+    async fn fetch(
+        &self,
+        url: &str,
+        part_path: &Path,
+        progress: &ProgressService,
+        token: &CancellationToken,
+    ) -> Result<(), Error> {
+        let mut attempt = 0;
+        loop {
+            match self.fetch_attempt(url, part_path, progress, token).await {
+                Err(e) if is_retryable(&e) && attempt < self.network.retries => {
+                    let delay = RETRY_DELAY
+                        .saturating_mul(2u32.saturating_pow(attempt))
+                        .min(MAX_RETRY_DELAY);
+                    attempt += 1;
+                    run_cancellable(token, async {
+                        tokio::time::sleep(delay).await;
+                        Ok(())
+                    })
+                    .await?;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn fetch_attempt(
+        &self,
+        url: &str,
+        part_path: &Path,
+        progress: &ProgressService,
+        token: &CancellationToken,
+    ) -> Result<(), Error> {
+        let bytes_present = fs::metadata(part_path)
+            .map(|metadata| metadata.len())
+            .unwrap_or_default();
+        let validator_path = get_validator_path(part_path);
+        let validator = fs::read_to_string(&validator_path).ok();
+        let mut request = self.github_service.client().get(url);
+        // The server only sends the rest of the file if it hasn't changed since the part file was started
+        if let (true, Some(validator)) = (bytes_present > 0, validator) {
+            request = request
+                .header(RANGE, format!("bytes={}-", bytes_present))
+                .header(IF_RANGE, validator);
+        }
+        let mut response =
+            run_cancellable(token, async { request.send().await.map_err(network_error) }).await?;
+
+        if response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+            // The part file doesn't match the remote file anymore, start over
+            remove_path(part_path)?;
+            return Err(Error::NetworkError(
+                "the download could not be resumed".to_string(),
+            ));
+        }
+        check_response_status(&response)?;
+
+        let is_resumed = response.status() == StatusCode::PARTIAL_CONTENT;
+        if is_resumed && get_range_start(&response) != Some(bytes_present) {
+            remove_path(part_path)?;
+            return Err(Error::NetworkError(
+                "the download could not be resumed".to_string(),
+            ));
+        }
+        let mut file = if is_resumed {
+            OpenOptions::new().append(true).open(part_path)?
+        } else {
+            match get_validator(&response) {
+                Some(validator) => fs::write(&validator_path, validator)?,
+                None => remove_path(&validator_path)?,
+            }
+            File::create(part_path)?
+        };
+        let mut bytes_done = if is_resumed { bytes_present } else { 0 };
+        let bytes_total = response.content_length().map(|length| length + bytes_done);
+
+        progress.report(ProgressPhase::Downloading, bytes_done, bytes_total);
+        let mut bytes_reported = bytes_done;
+        while let Some(chunk) = run_cancellable(token, async {
+            response.chunk().await.map_err(network_error)
+        })
+        .await?
+        {
+            file.write_all(&chunk)?;
+            bytes_done += chunk.len() as u64;
+            // Limit the amount of events sent to the frontend
+            if bytes_done - bytes_reported >= PROGRESS_INTERVAL {
+                progress.report(ProgressPhase::Downloading, bytes_done, bytes_total);
+                bytes_reported = bytes_done;
+            }
+        }
+        progress.report(ProgressPhase::Downloading, bytes_done, bytes_total);
+
+        if bytes_total.is_some_and(|bytes_total| bytes_done < bytes_total) {
+            return Err(Error::NetworkError("the download ended early".to_string()));
+        }
+
+        Ok(())
+    }
+
+    async fn verify_checksum(
+        &self,
+        plugin_name: &str,
+        tag: &str,
+        asset: &str,
+        actual: &str,
+        token: &CancellationToken,
+    ) -> Result<(), Error> {
+        // A checksum in the catalog takes precedence over the one published next to the asset
+        let catalog_checksum = self
+            .checksums
+            .get(plugin_name)
+            .and_then(|checksums| checksums.get(tag))
+            .and_then(|checksums| checksums.get(asset))
+            .cloned();
+        let expected = match catalog_checksum {
+            Some(checksum) => Some(checksum),
+            None => {
+                run_cancellable(
+                    token,
+                    self.github_service.get_checksum(plugin_name, tag, asset),
+                )
+                .await?
+            }
+        };
+
+        match expected {
+            Some(expected) if expected.eq_ignore_ascii_case(actual) => Ok(()),
+            Some(expected) => Err(Error::ChecksumMismatch {
+                asset: asset.to_string(),
+                expected,
+                actual: actual.to_string(),
+            }),
+            None if self.verification.require_checksums => {
+                Err(Error::MissingChecksum(asset.to_string()))
+            }
+            None => Ok(()),
+        }
+    }
+
+    async fn verify_signature(
+        &self,
+        file_path: &Path,
+        plugin_name: &str,
+        tag: &str,
+        asset: &str,
+        token: &CancellationToken,
+    ) -> Result<(), Error> {
+        let signature = run_cancellable(
+            token,
+            self.github_service.get_signature(plugin_name, tag, asset),
+        )
+        .await?;
+
+        match signature {
+            Some(signature) => self.signature_service.verify(file_path, asset, &signature),
+            None if self.verification.require_signatures
+                || self.signed_plugins.contains(plugin_name) =>
+            {
+                Err(Error::MissingSignature(asset.to_string()))
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+fn network_error(error: reqwest::Error) -> Error {
+    Error::NetworkError(error.to_string())
+}
+
+fn get_validator_path(part_path: &Path) -> PathBuf {
+    part_path.with_extension("validator")
+}
+
+// Weak ETags can't be used in If-Range, the modification date works as a fallback
+fn get_validator(response: &reqwest::Response) -> Option<String> {
+    let headers = response.headers();
+    let etag = headers
+        .get(ETAG)
+        .and_then(|etag| etag.to_str().ok())
+        .filter(|etag| !etag.starts_with("W/"));
+
+    etag.or_else(|| {
+        headers
+            .get(LAST_MODIFIED)
+            .and_then(|last_modified| last_modified.to_str().ok())
+    })
+    .map(|validator| validator.to_string())
+}
+
+// Content-Range looks like "bytes 100-199/200"
+fn get_range_start(response: &reqwest::Response) -> Option<u64> {
+    let content_range = response.headers().get(CONTENT_RANGE)?.to_str().ok()?;
+    let (start, _) = content_range.strip_prefix("bytes ")?.split_once('-')?;
+
+    start.trim().parse().ok()
+}
@@ -0,0 +1,290 @@
+use super::github_release::GithubRelease;
+use super::utils::check_response_status;
+use super::Error;
+use crate::settings_controller::GithubSettings;
+use reqwest::{header::ACCEPT, StatusCode};
+use serde::de::DeserializeOwned;
+
+pub struct GithubService {
+    client: reqwest::Client,
+    api_url: String,
+    download_url: String,
+    owner: String,
+}
+
+impl GithubService {
+    pub fn new(settings: &GithubSettings, client: reqwest::Client) -> Self {
+        Self {
+            client,
+            api_url: settings.api_url.trim_end_matches('/').to_string(),
+            download_url: settings.download_url.trim_end_matches('/').to_string(),
+            owner: settings.owner.clone(),
+        }
+    }
+
+    pub fn client(&self) -> &reqwest::Client {
+        &self.client
+    }
+
+    pub async fn get_latest_release_tag(&self, plugin_name: &str) -> Result<String, Error> {
+        let url = format!(
+            "{0}/repos/{1}/{2}/releases/latest",
+            self.api_url, self.owner, plugin_name
+        );
+
+        // Unauthenticated API requests are rate limited, the release page still works once the limit is used up
+        match self.get_json::<GithubRelease>(url, plugin_name).await {
+            Ok(release) => Ok(Self::validate_tag(&release.tag_name)?.to_string()),
+            Err(Error::RateLimited) => self.get_redirected_release_tag(plugin_name).await,
+            Err(e) => Err(e),
+        }
+    }
+
+    pub async fn get_releases(&self, plugin_name: &str) -> Result<Vec<GithubRelease>, Error> {
+        let url = format!(
+            "{0}/repos/{1}/{2}/releases?per_page=100",
+            self.api_url, self.owner, plugin_name
+        );
+
+        self.get_json(url, plugin_name).await
+    }
+
+    pub async fn get_checksum(
+        &self,
+        plugin_name: &str,
+        tag: &str,
+        asset: &str,
+    ) -> Result<Option<String>, Error> {
+        let body = match self.get_sidecar(plugin_name, tag, asset, "sha256").await? {
+            Some(body) => body,
+            None => return Ok(None),
+        };
+
+        // Sidecar files use the sha256sum format, the checksum followed by the file name
+        match body.split_whitespace().next() {
+            Some(checksum)
+                if checksum.len() == 64 && checksum.chars().all(|c| c.is_ascii_hexdigit()) =>
+            {
+                Ok(Some(checksum.to_lowercase()))
+            }
+            _ => Err(Error::InvalidChecksum(asset.to_string())),
+        }
+    }
+
+    pub async fn get_signature(
+        &self,
+        plugin_name: &str,
+        tag: &str,
+        asset: &str,
+    ) -> Result<Option<String>, Error> {
+        self.get_sidecar(plugin_name, tag, asset, "minisig").await
+    }
+
+    pub fn get_download_url(&self, plugin_name: &str, tag: &str, asset: &str) -> String {
+        format!(
+            "{0}/{1}/{2}/releases/download/{3}/{4}",
+            self.download_url, self.owner, plugin_name, tag, asset
+        )
+    }
+
+    // Tags end up in the download url, so they can't contain path separators
+    pub fn validate_tag(tag: &str) -> Result<&str, Error> {
+        let is_valid = !tag.is_empty()
+            && tag != "."
+            && tag != ".."
+            && !tag
+                .chars()
+                .any(|c| c == '/' || c == '\\' || c == '?' || c == '#' || c.is_whitespace());
+
+        if is_valid {
+            Ok(tag)
+        } else {
+            Err(Error::InvalidReleaseTag(tag.to_string()))
+        }
+    }
+
+    pub fn get_version(tag: &str) -> String {
+        tag.trim_start_matches(['v', 'V']).to_string()
+    }
+
+    // The release page redirects to the tag of the latest release
+    async fn get_redirected_release_tag(&self, plugin_name: &str) -> Result<String, Error> {
+        let url = format!(
+            "{0}/{1}/{2}/releases/latest",
+            self.download_url, self.owner, plugin_name
+        );
+        let response = self.client.head(url).send().await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(Error::NoReleaseFound(plugin_name.to_string()));
+        }
+        check_response_status(&response)?;
+
+        // Repositories without releases redirect to the release list instead
+        match response.url().path().rsplit_once("/releases/tag/") {
+            Some((_, tag)) => Ok(Self::validate_tag(tag)?.to_string()),
+            None => Err(Error::NoReleaseFound(plugin_name.to_string())),
+        }
+    }
+
+    async fn get_sidecar(
+        &self,
+        plugin_name: &str,
+        tag: &str,
+        asset: &str,
+        extension: &str,
+    ) -> Result<Option<String>, Error> {
+        let url = format!(
+            "{}.{}",
+            self.get_download_url(plugin_name, tag, asset),
+            extension
+        );
+        let response = self.client.get(url).send().await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        check_response_status(&response)?;
+        let body = response.text().await?;
+
+        Ok(Some(body))
+    }
+
+    async fn get_json<T: DeserializeOwned>(
+        &self,
+        url: String,
+        plugin_name: &str,
+    ) -> Result<T, Error> {
+        let response = self
+            .client
+            .get(url)
+            .header(ACCEPT, "application/vnd.github+json")
+            .send()
+            .await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(Error::NoReleaseFound(plugin_name.to_string()));
+        }
+        check_response_status(&response)?;
+        let body = response.bytes().await?;
+
+        Ok(serde_json::from_slice(&body)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread::{self, JoinHandle};
+
+    // A stub server that answers each connection with the next canned response and
+    // hands back the request lines it received
+    fn serve(responses: Vec<String>) -> (String, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            responses
+                .into_iter()
+                .map(|response| {
+                    let (mut stream, _) = listener.accept().unwrap();
+                    let mut buffer = [0; 4096];
+                    let length = stream.read(&mut buffer).unwrap();
+                    let request = String::from_utf8_lossy(&buffer[..length]);
+                    let request_line = request.lines().next().unwrap_or_default().to_string();
+                    stream.write_all(response.as_bytes()).unwrap();
+                    request_line
+                })
+                .collect()
+        });
+
+        (url, server)
+    }
+
+    fn response(status: &str, headers: &[&str], body: &str) -> String {
+        let mut response = format!("HTTP/1.1 {}\r\nConnection: close\r\n", status);
+        for header in headers {
+            response.push_str(&format!("{}\r\n", header));
+        }
+        response.push_str(&format!("Content-Length: {}\r\n\r\n{}", body.len(), body));
+        response
+    }
+
+    fn get_github_service(url: &str) -> GithubService {
+        let settings = GithubSettings {
+            api_url: format!("{}/", url),
+            download_url: url.to_string(),
+            owner: "davemollen".to_string(),
+        };
+
+        GithubService::new(&settings, reqwest::Client::new())
+    }
+
+    #[test]
+    fn get_latest_release_tag_reads_the_releases_api() {
+        let (url, server) = serve(vec![response(
+            "200 OK",
+            &["Content-Type: application/json"],
+            r#"{"tag_name":"v1.2.0","name":"v1.2.0"}"#,
+        )]);
+        let github_service = get_github_service(&url);
+
+        let tag = tauri::async_runtime::block_on(github_service.get_latest_release_tag("dm-Test"));
+
+        assert_eq!(tag.unwrap(), "v1.2.0");
+        assert_eq!(
+            server.join().unwrap(),
+            vec!["GET /repos/davemollen/dm-Test/releases/latest HTTP/1.1"]
+        );
+    }
+
+    #[test]
+    fn get_latest_release_tag_falls_back_to_the_release_page_when_rate_limited() {
+        let (url, server) = serve(vec![
+            response("403 Forbidden", &["x-ratelimit-remaining: 0"], ""),
+            response(
+                "302 Found",
+                &["Location: /davemollen/dm-Test/releases/tag/v2.0.0"],
+                "",
+            ),
+            response("200 OK", &[], ""),
+        ]);
+        let github_service = get_github_service(&url);
+
+        let tag = tauri::async_runtime::block_on(github_service.get_latest_release_tag("dm-Test"));
+
+        assert_eq!(tag.unwrap(), "v2.0.0");
+        assert_eq!(
+            server.join().unwrap(),
+            vec![
+                "GET /repos/davemollen/dm-Test/releases/latest HTTP/1.1",
+                "HEAD /davemollen/dm-Test/releases/latest HTTP/1.1",
+                "HEAD /davemollen/dm-Test/releases/tag/v2.0.0 HTTP/1.1",
+            ]
+        );
+    }
+
+    #[test]
+    fn get_latest_release_tag_reports_missing_releases() {
+        let (url, server) = serve(vec![response("404 Not Found", &[], "")]);
+        let github_service = get_github_service(&url);
+
+        let tag = tauri::async_runtime::block_on(github_service.get_latest_release_tag("dm-Test"));
+
+        assert!(matches!(tag, Err(Error::NoReleaseFound(plugin)) if plugin == "dm-Test"));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn get_latest_release_tag_rejects_unsafe_tags() {
+        let (url, server) = serve(vec![response(
+            "200 OK",
+            &["Content-Type: application/json"],
+            r#"{"tag_name":"../../v1.0.0"}"#,
+        )]);
+        let github_service = get_github_service(&url);
+
+        let tag = tauri::async_runtime::block_on(github_service.get_latest_release_tag("dm-Test"));
+
+        assert!(matches!(tag, Err(Error::InvalidReleaseTag(_))));
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,77 @@
+use super::Error;
+use crate::settings_controller::NetworkSettings;
+use reqwest::{Certificate, Client, NoProxy, Proxy};
+use std::fs;
+use std::sync::Mutex;
+use std::time::Duration;
+
+const USER_AGENT: &str = "dm-plugin-manager";
+const PEM_END: &str = "-----END CERTIFICATE-----";
+
+// Kept in the app state so every command shares one client and its connection pool
+#[derive(Default)]
+pub struct HttpService {
+    client: Mutex<Option<(NetworkSettings, Client)>>,
+}
+
+impl HttpService {
+    // The client is only rebuilt when the network settings have changed since the last call
+    pub fn get_client(&self, settings: &NetworkSettings) -> Result<Client, Error> {
+        let mut cached_client = self
+            .client
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some((cached_settings, client)) = cached_client.as_ref() {
+            if cached_settings == settings {
+                return Ok(client.clone());
+            }
+        }
+
+        let client = Self::create_client(settings)?;
+        *cached_client = Some((settings.clone(), client.clone()));
+
+        Ok(client)
+    }
+
+    fn create_client(settings: &NetworkSettings) -> Result<Client, Error> {
+        let mut builder = Client::builder()
+            .user_agent(USER_AGENT)
+            .connect_timeout(Duration::from_secs(settings.connect_timeout_secs))
+            .read_timeout(Duration::from_secs(settings.read_timeout_secs));
+
+        if let Some(proxy_url) = settings
+            .proxy
+            .as_deref()
+            .filter(|url| !url.trim().is_empty())
+        {
+            let proxy = Proxy::all(proxy_url.trim())
+                .map_err(|_| Error::InvalidProxy(proxy_url.to_string()))?
+                .no_proxy(NoProxy::from_string(&settings.no_proxy.join(",")));
+            builder = builder.proxy(proxy);
+        }
+        for path in &settings.ca_certificates {
+            for certificate in Self::read_certificates(path)? {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+
+        Ok(builder.build()?)
+    }
+
+    // A file can hold a whole bundle, which native-tls won't parse in one go
+    fn read_certificates(path: &str) -> Result<Vec<Certificate>, Error> {
+        let contents =
+            fs::read_to_string(path).map_err(|_| Error::InvalidCertificate(path.to_string()))?;
+        let certificates = contents
+            .split_inclusive(PEM_END)
+            .filter(|pem| pem.contains(PEM_END))
+            .map(|pem| Certificate::from_pem(pem.trim().as_bytes()))
+            .collect::<Result<Vec<Certificate>, reqwest::Error>>()
+            .map_err(|_| Error::InvalidCertificate(path.to_string()))?;
+        if certificates.is_empty() {
+            return Err(Error::InvalidCertificate(path.to_string()));
+        }
+
+        Ok(certificates)
+    }
+}
@@ -0,0 +1,56 @@
+use super::ssh_service::SshError;
+use russh::keys::{encode_pkcs8_pem, key::KeyPair, load_secret_key, PublicKeyBase64};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+pub struct KeyService {
+    key_path: PathBuf,
+}
+
+impl KeyService {
+    const FILE_NAME: &'static str = "id_ed25519";
+    const KEY_COMMENT: &'static str = "dm-plugin-manager";
+
+    pub fn new(config_folder: PathBuf) -> Self {
+        Self {
+            key_path: config_folder.join(Self::FILE_NAME),
+        }
+    }
+
+    pub fn load(key_path: impl AsRef<Path>, passphrase: Option<&str>) -> Result<KeyPair, SshError> {
+        let key_pair = load_secret_key(key_path, passphrase)?;
+
+        Ok(key_pair)
+    }
+
+    pub fn load_or_generate(&self) -> Result<KeyPair, SshError> {
+        if self.key_path.exists() {
+            return Self::load(&self.key_path, None);
+        }
+
+        let key_pair = KeyPair::generate_ed25519().ok_or(SshError::KeyGenerationFailed)?;
+        if let Some(folder) = self.key_path.parent() {
+            fs::create_dir_all(folder)?;
+        }
+        let file = File::create(&self.key_path)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            file.set_permissions(fs::Permissions::from_mode(0o600))?;
+        }
+        encode_pkcs8_pem(&key_pair, file)?;
+
+        Ok(key_pair)
+    }
+
+    pub fn public_key_line(key_pair: &KeyPair) -> Result<String, SshError> {
+        let public_key = key_pair.clone_public_key()?;
+
+        Ok(format!(
+            "{} {} {}",
+            public_key.name(),
+            public_key.public_key_base64(),
+            Self::KEY_COMMENT
+        ))
+    }
+}
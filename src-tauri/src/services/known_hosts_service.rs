@@ -0,0 +1,64 @@
+use super::settings_service::SettingsError;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::path::PathBuf;
+
+pub struct KnownHostsService {
+    file_path: PathBuf,
+}
+
+impl KnownHostsService {
+    const FILE_NAME: &'static str = "known_hosts.json";
+
+    pub fn new(config_folder: PathBuf) -> Self {
+        Self {
+            file_path: config_folder.join(Self::FILE_NAME),
+        }
+    }
+
+    pub fn get(&self, address: &str) -> Result<Option<String>, SettingsError> {
+        let known_hosts = self.load()?;
+
+        Ok(known_hosts.get(address).cloned())
+    }
+
+    pub fn insert(&self, address: &str, fingerprint: &str) -> Result<(), SettingsError> {
+        let mut known_hosts = self.load()?;
+        known_hosts.insert(address.to_string(), fingerprint.to_string());
+
+        self.save(&known_hosts)
+    }
+
+    pub fn remove(&self, address: &str) -> Result<(), SettingsError> {
+        let mut known_hosts = self.load()?;
+        if known_hosts.remove(address).is_none() {
+            return Ok(());
+        }
+
+        self.save(&known_hosts)
+    }
+
+    fn load(&self) -> Result<HashMap<String, String>, SettingsError> {
+        if !self.file_path.exists() {
+            return Ok(HashMap::new());
+        }
+
+        let file = File::open(&self.file_path)?;
+        let known_hosts = serde_json::from_reader(file)?;
+
+        Ok(known_hosts)
+    }
+
+    fn save(&self, known_hosts: &HashMap<String, String>) -> Result<(), SettingsError> {
+        if let Some(folder) = self.file_path.parent() {
+            fs::create_dir_all(folder)?;
+        }
+
+        let temp_file_path = self.file_path.with_extension("json.tmp");
+        let file = File::create(&temp_file_path)?;
+        serde_json::to_writer_pretty(file, known_hosts)?;
+        fs::rename(&temp_file_path, &self.file_path)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,317 @@
+use super::plugin_format::PluginFormat;
+use super::Error;
+use std::env::consts::ARCH;
+use std::path::{Path, PathBuf};
+use tauri::utils::platform::Target;
+
+pub struct LayoutService;
+
+impl LayoutService {
+    // Checks the archive contents up front, so a changed release layout fails with a clear error
+    pub fn validate(
+        files: &[PathBuf],
+        bundle_prefix: &Path,
+        plugin_format: &PluginFormat,
+        asset: &str,
+    ) -> Result<(), Error> {
+        Self::validate_for_target(
+            files,
+            bundle_prefix,
+            plugin_format,
+            Target::current(),
+            asset,
+        )
+    }
+
+    fn validate_for_target(
+        files: &[PathBuf],
+        bundle_prefix: &Path,
+        plugin_format: &PluginFormat,
+        target: Target,
+        asset: &str,
+    ) -> Result<(), Error> {
+        let bundle_files: Vec<&Path> = files
+            .iter()
+            .filter_map(|file| file.strip_prefix(bundle_prefix).ok())
+            .collect();
+        let invalid_layout = |reason: String| Error::InvalidArchiveLayout {
+            asset: asset.to_string(),
+            reason,
+        };
+
+        if bundle_files.is_empty() {
+            return Err(invalid_layout(format!(
+                "{} is missing",
+                bundle_prefix.display()
+            )));
+        }
+
+        let linux_folder = format!("{}-linux", Self::get_vst3_architecture());
+        let linux_files = ["Contents", linux_folder.as_str(), "*.so"];
+        let linux_layout: [&[&str]; 1] = [&linux_files];
+        let is_single_file = bundle_files.iter().any(|file| file.as_os_str().is_empty());
+        let required_files: &[&[&str]] = match (plugin_format, target) {
+            (PluginFormat::ModAudio, _) => &[&["manifest.ttl"], &["*.so"]],
+            (PluginFormat::VST3 | PluginFormat::CLAP, Target::MacOS) => {
+                &[&["Contents", "Info.plist"], &["Contents", "MacOS", "*"]]
+            }
+            (PluginFormat::VST3, Target::Windows) if is_single_file => &[],
+            (PluginFormat::VST3, Target::Windows) => &[&["Contents", "*-win", "*.vst3"]],
+            (PluginFormat::VST3, _) => &linux_layout,
+            (PluginFormat::CLAP, _) if is_single_file => &[],
+            (PluginFormat::CLAP, _) => {
+                return Err(invalid_layout(format!(
+                    "{} should be a single file",
+                    bundle_prefix.display()
+                )))
+            }
+        };
+
+        for pattern in required_files {
+            if !bundle_files.iter().any(|file| Self::matches(file, pattern)) {
+                return Err(invalid_layout(format!(
+                    "{} is missing",
+                    bundle_prefix.join(pattern.join("/")).display()
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    // VST3 bundles use their own names for some architectures
+    fn get_vst3_architecture() -> &'static str {
+        match ARCH {
+            "x86" => "i386",
+            "arm" => "armv7l",
+            arch => arch,
+        }
+    }
+
+    // Patterns only support a leading wildcard per path component, like *.so
+    fn matches(file: &Path, pattern: &[&str]) -> bool {
+        let components: Vec<String> = file
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy().to_string())
+            .collect();
+        if components.len() != pattern.len() {
+            return false;
+        }
+
+        components
+            .iter()
+            .zip(pattern)
+            .all(|(component, pattern)| match pattern.strip_prefix('*') {
+                Some(suffix) => component.ends_with(suffix),
+                None => component == pattern,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ASSET: &str = "dm-Test.tar.gz";
+
+    fn paths(files: &[&str]) -> Vec<PathBuf> {
+        files.iter().map(PathBuf::from).collect()
+    }
+
+    fn validate(
+        files: &[&str],
+        bundle_prefix: &str,
+        plugin_format: PluginFormat,
+        target: Target,
+    ) -> Result<(), Error> {
+        LayoutService::validate_for_target(
+            &paths(files),
+            Path::new(bundle_prefix),
+            &plugin_format,
+            target,
+            ASSET,
+        )
+    }
+
+    fn assert_invalid_layout(result: Result<(), Error>, missing: &str) {
+        match result {
+            Err(Error::InvalidArchiveLayout { asset, reason }) => {
+                assert_eq!(asset, ASSET);
+                assert!(reason.contains(missing), "{}", reason);
+            }
+            result => panic!("expected an invalid layout, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn matches_wildcards_per_component() {
+        let pattern = ["Contents", "*-win", "*.vst3"];
+
+        assert!(LayoutService::matches(
+            Path::new("Contents/x86_64-win/dm-Test.vst3"),
+            &pattern
+        ));
+        assert!(!LayoutService::matches(
+            Path::new("Contents/x86_64-win/dm-Test.dll"),
+            &pattern
+        ));
+        assert!(!LayoutService::matches(
+            Path::new("Contents/x86_64-win/nested/dm-Test.vst3"),
+            &pattern
+        ));
+        assert!(!LayoutService::matches(
+            Path::new("Resources/x86_64-win/dm-Test.vst3"),
+            &pattern
+        ));
+        assert!(!LayoutService::matches(Path::new(""), &["*"]));
+    }
+
+    #[test]
+    fn validates_single_file_clap_plugins() {
+        for target in [Target::Windows, Target::Linux] {
+            assert!(validate(
+                &["dm-Test/dm-Test.clap"],
+                "dm-Test/dm-Test.clap",
+                PluginFormat::CLAP,
+                target
+            )
+            .is_ok());
+            assert_invalid_layout(
+                validate(
+                    &["dm-Test/dm-Test.clap/dm-Test.so"],
+                    "dm-Test/dm-Test.clap",
+                    PluginFormat::CLAP,
+                    target,
+                ),
+                "should be a single file",
+            );
+        }
+    }
+
+    #[test]
+    fn validates_mac_os_bundles() {
+        let files = [
+            "dm-Test/dm-Test.vst3/Contents/Info.plist",
+            "dm-Test/dm-Test.vst3/Contents/MacOS/dm-Test",
+        ];
+
+        for plugin_format in [PluginFormat::VST3, PluginFormat::CLAP] {
+            assert!(validate(
+                &files,
+                "dm-Test/dm-Test.vst3",
+                plugin_format.clone(),
+                Target::MacOS
+            )
+            .is_ok());
+            assert_invalid_layout(
+                validate(
+                    &files[..1],
+                    "dm-Test/dm-Test.vst3",
+                    plugin_format,
+                    Target::MacOS,
+                ),
+                "dm-Test/dm-Test.vst3/Contents/MacOS/*",
+            );
+        }
+    }
+
+    #[test]
+    fn validates_windows_vst3_plugins() {
+        assert!(validate(
+            &["dm-Test/dm-Test.vst3/Contents/x86_64-win/dm-Test.vst3"],
+            "dm-Test/dm-Test.vst3",
+            PluginFormat::VST3,
+            Target::Windows
+        )
+        .is_ok());
+        assert!(validate(
+            &["dm-Test/dm-Test.vst3"],
+            "dm-Test/dm-Test.vst3",
+            PluginFormat::VST3,
+            Target::Windows
+        )
+        .is_ok());
+        assert_invalid_layout(
+            validate(
+                &["dm-Test/dm-Test.vst3/Contents/x86_64-win/dm-Test.dll"],
+                "dm-Test/dm-Test.vst3",
+                PluginFormat::VST3,
+                Target::Windows,
+            ),
+            "dm-Test/dm-Test.vst3/Contents/*-win/*.vst3",
+        );
+    }
+
+    #[test]
+    fn validates_linux_vst3_plugins() {
+        let architecture = LayoutService::get_vst3_architecture();
+        let plugin_path = format!(
+            "dm-Test/dm-Test.vst3/Contents/{}-linux/dm-Test.so",
+            architecture
+        );
+
+        assert!(validate(
+            &[&plugin_path],
+            "dm-Test/dm-Test.vst3",
+            PluginFormat::VST3,
+            Target::Linux
+        )
+        .is_ok());
+        assert_invalid_layout(
+            validate(
+                &["dm-Test/dm-Test.vst3/Contents/unknown-linux/dm-Test.so"],
+                "dm-Test/dm-Test.vst3",
+                PluginFormat::VST3,
+                Target::Linux,
+            ),
+            &format!("Contents/{}-linux/*.so", architecture),
+        );
+    }
+
+    #[test]
+    fn validates_lv2_bundles() {
+        let files = [
+            "dm-Test/dm-Test.lv2/manifest.ttl",
+            "dm-Test/dm-Test.lv2/dm-Test.so",
+        ];
+
+        assert!(validate(
+            &files,
+            "dm-Test/dm-Test.lv2",
+            PluginFormat::ModAudio,
+            Target::Linux
+        )
+        .is_ok());
+        assert_invalid_layout(
+            validate(
+                &files[1..],
+                "dm-Test/dm-Test.lv2",
+                PluginFormat::ModAudio,
+                Target::Linux,
+            ),
+            "manifest.ttl",
+        );
+        assert_invalid_layout(
+            validate(
+                &files[..1],
+                "dm-Test/dm-Test.lv2",
+                PluginFormat::ModAudio,
+                Target::Linux,
+            ),
+            "*.so",
+        );
+    }
+
+    #[test]
+    fn rejects_archives_without_the_bundle() {
+        assert_invalid_layout(
+            validate(
+                &["other/other.vst3/Contents/Info.plist"],
+                "dm-Test/dm-Test.vst3",
+                PluginFormat::VST3,
+                Target::MacOS,
+            ),
+            "dm-Test/dm-Test.vst3 is missing",
+        );
+    }
+}
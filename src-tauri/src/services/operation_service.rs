@@ -0,0 +1,38 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+#[derive(Default)]
+pub struct OperationService {
+    tokens: Mutex<HashMap<String, CancellationToken>>,
+}
+
+impl OperationService {
+    pub fn start(&self, operation_id: Option<&str>) -> CancellationToken {
+        let token = CancellationToken::new();
+        if let (Some(operation_id), Ok(mut tokens)) = (operation_id, self.tokens.lock()) {
+            tokens.insert(operation_id.to_string(), token.clone());
+        }
+
+        token
+    }
+
+    pub fn finish(&self, operation_id: Option<&str>) {
+        if let (Some(operation_id), Ok(mut tokens)) = (operation_id, self.tokens.lock()) {
+            tokens.remove(operation_id);
+        }
+    }
+
+    pub fn cancel(&self, operation_id: &str) -> bool {
+        match self.tokens.lock() {
+            Ok(tokens) => match tokens.get(operation_id) {
+                Some(token) => {
+                    token.cancel();
+                    true
+                }
+                None => false,
+            },
+            Err(_) => false,
+        }
+    }
+}
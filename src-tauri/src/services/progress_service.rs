@@ -0,0 +1,42 @@
+use super::plugin_format::PluginFormat;
+use super::progress::{PluginProgress, ProgressPhase};
+use tauri::{AppHandle, Emitter};
+
+pub struct ProgressService {
+    handle: AppHandle,
+    plugin: String,
+    format: String,
+}
+
+impl ProgressService {
+    const EVENT_NAME: &'static str = "plugin-progress";
+
+    pub fn new(handle: &AppHandle, plugin: &str, format: &PluginFormat) -> Self {
+        Self {
+            handle: handle.clone(),
+            plugin: plugin.to_string(),
+            format: format.to_string(),
+        }
+    }
+
+    pub fn report(&self, phase: ProgressPhase, bytes_done: u64, bytes_total: Option<u64>) {
+        let payload = PluginProgress {
+            plugin: self.plugin.clone(),
+            format: self.format.clone(),
+            phase,
+            bytes_done,
+            bytes_total,
+        };
+
+        // Progress is informational, a failing emit shouldn't fail the install
+        let _ = self.handle.emit(Self::EVENT_NAME, payload);
+    }
+
+    pub fn start(&self, phase: ProgressPhase) {
+        self.report(phase, 0, None);
+    }
+
+    pub fn finish(&self) {
+        self.report(ProgressPhase::Done, 0, None);
+    }
+}
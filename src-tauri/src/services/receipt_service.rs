@@ -0,0 +1,106 @@
+use super::install_receipt::InstallReceipt;
+use super::plugins::PluginVersion;
+use super::utils::{get_receipt_path, remove_path};
+use super::Error;
+use crate::mod_plugin_controller::{self, SshService};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::Path;
+
+const MODULE_INFO_PATH: &str = "Contents/Resources/moduleinfo.json";
+const INFO_PLIST_PATH: &str = "Contents/Info.plist";
+
+pub struct ReceiptService;
+
+impl ReceiptService {
+    pub fn write(plugin_path: &Path, receipt: &InstallReceipt) -> Result<(), io::Error> {
+        let receipt_path = get_receipt_path(plugin_path);
+        let contents = serde_json::to_vec_pretty(receipt)?;
+        fs::write(receipt_path, contents)
+    }
+
+    pub fn read(plugin_path: &Path) -> Option<InstallReceipt> {
+        let file = File::open(get_receipt_path(plugin_path)).ok()?;
+        serde_json::from_reader(file).ok()
+    }
+
+    pub fn remove(plugin_path: &Path) -> Result<(), io::Error> {
+        remove_path(&get_receipt_path(plugin_path))
+    }
+
+    pub fn get_installed_version(plugin_path: &Path) -> Option<PluginVersion> {
+        if let Some(receipt) = Self::read(plugin_path) {
+            return Some(receipt.into());
+        }
+
+        // Plugins installed by hand or by an older version of this app don't have a receipt
+        Self::read_module_info_version(plugin_path)
+            .or_else(|| Self::read_info_plist_version(plugin_path))
+            .map(|version| PluginVersion {
+                version,
+                tag: None,
+                installed_at: None,
+            })
+    }
+
+    // All receipts of a MOD device are kept in a single file in its plugin folder
+    pub async fn read_mod_receipts(
+        ssh_service: &SshService,
+    ) -> Result<HashMap<String, InstallReceipt>, Error> {
+        let contents = mod_plugin_controller::read_mod_receipts(ssh_service).await?;
+        if contents.trim().is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        Ok(serde_json::from_str(&contents).unwrap_or_default())
+    }
+
+    pub async fn update_mod_receipts(
+        ssh_service: &SshService,
+        installed: Vec<(String, InstallReceipt)>,
+        removed: &[String],
+    ) -> Result<(), Error> {
+        if installed.is_empty() && removed.is_empty() {
+            return Ok(());
+        }
+
+        let mut receipts = Self::read_mod_receipts(ssh_service).await?;
+        receipts.retain(|plugin_name, _| !removed.contains(plugin_name));
+        receipts.extend(installed);
+        let contents = serde_json::to_vec_pretty(&receipts)?;
+        mod_plugin_controller::write_mod_receipts(ssh_service, &contents).await?;
+
+        Ok(())
+    }
+
+    fn read_module_info_version(plugin_path: &Path) -> Option<String> {
+        let file = File::open(plugin_path.join(MODULE_INFO_PATH)).ok()?;
+        let module_info: serde_json::Value = serde_json::from_reader(file).ok()?;
+        module_info
+            .get("Version")
+            .and_then(|version| version.as_str())
+            .map(|version| version.to_string())
+    }
+
+    fn read_info_plist_version(plugin_path: &Path) -> Option<String> {
+        let mut contents = String::new();
+        File::open(plugin_path.join(INFO_PLIST_PATH))
+            .ok()?
+            .read_to_string(&mut contents)
+            .ok()?;
+
+        ["CFBundleShortVersionString", "CFBundleVersion"]
+            .iter()
+            .find_map(|key| Self::find_plist_string(&contents, key))
+    }
+
+    fn find_plist_string(contents: &str, key: &str) -> Option<String> {
+        let key_tag = format!("<key>{}</key>", key);
+        let rest = &contents[contents.find(&key_tag)? + key_tag.len()..];
+        let value = rest.trim_start().strip_prefix("<string>")?;
+        let value = value[..value.find("</string>")?].trim();
+
+        (!value.is_empty()).then(|| value.to_string())
+    }
+}
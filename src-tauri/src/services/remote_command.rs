@@ -0,0 +1,194 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum RemoteCommandError {
+    #[error("Refusing to use \"{0}\" as a plugin path")]
+    InvalidPluginPath(String),
+}
+
+#[derive(Clone, Debug)]
+pub struct RemoteCommand {
+    command: String,
+}
+
+impl RemoteCommand {
+    pub const PLUGIN_FOLDER: &'static str = ".lv2";
+
+    pub fn new(program: &str) -> Self {
+        Self {
+            command: quote(program),
+        }
+    }
+
+    pub fn arg(mut self, arg: impl AsRef<str>) -> Self {
+        self.command.push(' ');
+        self.command.push_str(&quote(arg.as_ref()));
+        self
+    }
+
+    pub fn args<I, S>(self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        args.into_iter().fold(self, |command, arg| command.arg(arg))
+    }
+
+    pub fn plugin_path_arg(self, path: impl AsRef<str>) -> Result<Self, RemoteCommandError> {
+        let plugin_path = get_plugin_path(path.as_ref())?;
+
+        Ok(self.arg(plugin_path))
+    }
+
+    pub fn append_to(mut self, file: impl AsRef<str>) -> Self {
+        self.command.push_str(" >> ");
+        self.command.push_str(&quote(file.as_ref()));
+        self
+    }
+
+    pub fn write_to(mut self, file: impl AsRef<str>) -> Self {
+        self.command.push_str(" > ");
+        self.command.push_str(&quote(file.as_ref()));
+        self
+    }
+
+    pub fn and(mut self, next: RemoteCommand) -> Self {
+        self.command = format!("{} && {}", self.command, next.command);
+        self
+    }
+
+    pub fn or(mut self, next: RemoteCommand) -> Self {
+        self.command = format!("({} || {})", self.command, next.command);
+        self
+    }
+}
+
+impl Display for RemoteCommand {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.command)
+    }
+}
+
+// Wraps the argument in single quotes unless it only contains characters the shell won't interpret
+pub fn quote(arg: &str) -> String {
+    let is_safe = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=@+,%".contains(c));
+
+    if is_safe {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+// Resolves a path relative to the plugin folder and rejects anything that could point outside of it
+pub fn get_plugin_path(path: &str) -> Result<String, RemoteCommandError> {
+    let invalid_path = || RemoteCommandError::InvalidPluginPath(path.to_string());
+
+    if path.starts_with('/') || path.starts_with('\\') || path.contains('\0') {
+        return Err(invalid_path());
+    }
+
+    let components: Vec<&str> = path
+        .split(['/', '\\'])
+        .filter(|component| !component.is_empty() && *component != ".")
+        .collect();
+    if components.is_empty() || components.contains(&"..") {
+        return Err(invalid_path());
+    }
+
+    Ok(format!(
+        "{}/{}",
+        RemoteCommand::PLUGIN_FOLDER,
+        components.join("/")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quote_leaves_safe_arguments_alone() {
+        assert_eq!(quote("Plugin.lv2"), "Plugin.lv2");
+        assert_eq!(quote(".lv2/dm-Plugin.lv2"), ".lv2/dm-Plugin.lv2");
+    }
+
+    #[test]
+    fn quote_wraps_hostile_arguments() {
+        assert_eq!(quote(""), "''");
+        assert_eq!(quote("My Plugin"), "'My Plugin'");
+        assert_eq!(quote("it's"), r"'it'\''s'");
+        assert_eq!(quote("a; rm -rf ~"), "'a; rm -rf ~'");
+        assert_eq!(quote("$(reboot)"), "'$(reboot)'");
+        assert_eq!(quote("`reboot`"), "'`reboot`'");
+        assert_eq!(quote("a\nreboot"), "'a\nreboot'");
+        assert_eq!(quote("'; reboot; '"), r"''\''; reboot; '\'''");
+    }
+
+    #[test]
+    fn get_plugin_path_resolves_inside_plugin_folder() {
+        assert_eq!(get_plugin_path("Plugin.lv2").unwrap(), ".lv2/Plugin.lv2");
+        assert_eq!(
+            get_plugin_path("./Plugin.lv2//manifest.ttl").unwrap(),
+            ".lv2/Plugin.lv2/manifest.ttl"
+        );
+        assert_eq!(
+            get_plugin_path(r"Plugin.lv2\manifest.ttl").unwrap(),
+            ".lv2/Plugin.lv2/manifest.ttl"
+        );
+    }
+
+    #[test]
+    fn get_plugin_path_rejects_escaping_paths() {
+        for path in [
+            "",
+            ".",
+            "..",
+            "../.ssh",
+            "a/../..",
+            "Plugin.lv2/../../etc",
+            r"a\..\..",
+            "/etc/passwd",
+            r"\etc\passwd",
+            "Plugin.lv2\0",
+        ] {
+            assert!(get_plugin_path(path).is_err(), "{:?} was accepted", path);
+        }
+    }
+
+    #[test]
+    fn plugin_path_arg_quotes_and_validates() {
+        let command = RemoteCommand::new("rm")
+            .arg("-rf")
+            .plugin_path_arg("My Plugin.lv2")
+            .unwrap();
+        assert_eq!(command.to_string(), "rm -rf '.lv2/My Plugin.lv2'");
+
+        assert!(RemoteCommand::new("rm")
+            .arg("-rf")
+            .plugin_path_arg("..")
+            .is_err());
+    }
+
+    #[test]
+    fn commands_compose_with_and_or() {
+        let command = RemoteCommand::new("test")
+            .arg("-f")
+            .arg("a b")
+            .and(RemoteCommand::new("cat").arg("a b"))
+            .or(RemoteCommand::new("true"));
+        assert_eq!(command.to_string(), "(test -f 'a b' && cat 'a b' || true)");
+
+        let command = RemoteCommand::new("mkdir")
+            .args(["-p", "$(x)"])
+            .and(RemoteCommand::new("echo").arg("`x`").append_to("log file"));
+        assert_eq!(
+            command.to_string(),
+            "mkdir -p '$(x)' && echo '`x`' >> 'log file'"
+        );
+    }
+}
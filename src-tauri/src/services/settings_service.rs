@@ -0,0 +1,52 @@
+use super::settings::Settings;
+use std::fs::{self, File};
+use std::path::PathBuf;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum SettingsError {
+    #[error("{0}")]
+    FileSystemError(#[from] std::io::Error),
+
+    #[error("Invalid settings file: {0}")]
+    SerializationError(#[from] serde_json::Error),
+}
+
+pub struct SettingsService {
+    file_path: PathBuf,
+}
+
+impl SettingsService {
+    const FILE_NAME: &'static str = "settings.json";
+
+    pub fn new(config_folder: PathBuf) -> Self {
+        Self {
+            file_path: config_folder.join(Self::FILE_NAME),
+        }
+    }
+
+    pub fn load(&self) -> Result<Settings, SettingsError> {
+        if !self.file_path.exists() {
+            return Ok(Settings::default());
+        }
+
+        let file = File::open(&self.file_path)?;
+        let settings = serde_json::from_reader(file)?;
+
+        Ok(settings)
+    }
+
+    pub fn save(&self, settings: &Settings) -> Result<(), SettingsError> {
+        if let Some(folder) = self.file_path.parent() {
+            fs::create_dir_all(folder)?;
+        }
+
+        // Write to a temporary file first so a crash can't leave a truncated settings file behind
+        let temp_file_path = self.file_path.with_extension("json.tmp");
+        let file = File::create(&temp_file_path)?;
+        serde_json::to_writer_pretty(file, settings)?;
+        fs::rename(&temp_file_path, &self.file_path)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,66 @@
+use super::Error;
+use minisign_verify::{PublicKey, Signature};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+pub struct SignatureService {
+    trusted_keys: Vec<PublicKey>,
+}
+
+impl SignatureService {
+    pub fn new(trusted_keys: &[String]) -> Self {
+        // Keys can be stored as the bare base64 key or as the full public key file
+        let trusted_keys = trusted_keys
+            .iter()
+            .filter_map(|key| {
+                PublicKey::from_base64(key.trim())
+                    .or_else(|_| PublicKey::decode(key))
+                    .ok()
+            })
+            .collect();
+
+        Self { trusted_keys }
+    }
+
+    pub fn verify(&self, file_path: &Path, asset: &str, signature: &str) -> Result<(), Error> {
+        let signature =
+            Signature::decode(signature).map_err(|_| Error::InvalidSignature(asset.to_string()))?;
+
+        // A valid signature for another file must not be accepted in place of this one
+        let signed_file = signature
+            .trusted_comment()
+            .split('\t')
+            .find_map(|field| field.strip_prefix("file:"));
+        if signed_file.is_some_and(|signed_file| signed_file != asset) {
+            return Err(Error::InvalidSignature(asset.to_string()));
+        }
+
+        // Only the key the signature was made with gets a verifier, legacy signatures aren't supported
+        let mut verifier = match self
+            .trusted_keys
+            .iter()
+            .find_map(|key| key.verify_stream(&signature).ok())
+        {
+            Some(verifier) => verifier,
+            None => return Err(Error::UntrustedSignature(asset.to_string())),
+        };
+
+        // The archive is hashed in chunks instead of being read into memory
+        let mut file = File::open(file_path)?;
+        let mut buffer = vec![0; CHUNK_SIZE];
+        loop {
+            let length = file.read(&mut buffer)?;
+            if length == 0 {
+                break;
+            }
+            verifier.update(&buffer[..length]);
+        }
+
+        verifier
+            .finalize()
+            .map_err(|_| Error::UntrustedSignature(asset.to_string()))
+    }
+}
@@ -1,6 +1,12 @@
+use super::plugins::ArrayBufferWithPath;
+use super::remote_command::RemoteCommand;
 use async_trait::async_trait;
 use russh::{client, keys::key, ChannelMsg, Disconnect};
-use std::{sync::Arc, time::Duration};
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tar::{Builder, EntryType, Header};
 use thiserror::Error;
 use tokio::{io::AsyncWriteExt, time::timeout};
 
@@ -20,37 +26,109 @@ pub enum SshError {
 
     #[error("Ssh write to buffer failed: {0}")]
     WriteError(#[from] std::io::Error),
+
+    #[error("Ssh key error: {0}")]
+    KeyError(#[from] russh::keys::Error),
+
+    #[error("Unable to generate a ssh key")]
+    KeyGenerationFailed,
+
+    #[error("Authentication with MOD failed")]
+    AuthenticationFailed,
+
+    #[error("The host key of {host} has changed (expected {expected}, got {actual})")]
+    HostKeyMismatch {
+        host: String,
+        expected: String,
+        actual: String,
+    },
 }
 
-struct ClientHandler;
+pub enum SshAuthentication<'a> {
+    Password(&'a str),
+    PublicKey(Arc<key::KeyPair>),
+}
+
+struct ClientHandler {
+    host: String,
+    pinned_fingerprint: Option<String>,
+    server_fingerprint: Arc<Mutex<Option<String>>>,
+}
 
 #[async_trait]
 impl client::Handler for ClientHandler {
-    type Error = russh::Error;
+    type Error = SshError;
 
     async fn check_server_key(
         &mut self,
-        _server_public_key: &key::PublicKey,
+        server_public_key: &key::PublicKey,
     ) -> Result<bool, Self::Error> {
-        Ok(true)
+        let fingerprint = server_public_key.fingerprint();
+
+        match &self.pinned_fingerprint {
+            Some(pinned_fingerprint) if *pinned_fingerprint != fingerprint => {
+                Err(SshError::HostKeyMismatch {
+                    host: self.host.clone(),
+                    expected: pinned_fingerprint.clone(),
+                    actual: fingerprint,
+                })
+            }
+            _ => {
+                if let Ok(mut server_fingerprint) = self.server_fingerprint.lock() {
+                    *server_fingerprint = Some(fingerprint);
+                }
+                Ok(true)
+            }
+        }
     }
 }
 
+#[derive(Clone)]
 pub struct SshService {
     client: Arc<client::Handle<ClientHandler>>,
+    host_key_fingerprint: String,
 }
 
 impl SshService {
     const CONNECTION_TIMEOUT: Duration = Duration::from_secs(3);
-
-    pub async fn connect(url: &str, username: &str, password: &str) -> Result<Self, SshError> {
+    const DEFAULT_FILE_MODE: u32 = 0o644;
+
+    pub async fn connect(
+        host: &str,
+        port: u16,
+        username: &str,
+        authentication: SshAuthentication<'_>,
+        pinned_fingerprint: Option<&str>,
+    ) -> Result<Self, SshError> {
         let future = async {
             let config = Arc::new(client::Config::default());
-            let mut session = client::connect(config, (url, 22), ClientHandler {}).await?;
-            session.authenticate_password(username, password).await?;
+            let server_fingerprint = Arc::new(Mutex::new(None));
+            let handler = ClientHandler {
+                host: format!("{}:{}", host, port),
+                pinned_fingerprint: pinned_fingerprint.map(str::to_string),
+                server_fingerprint: server_fingerprint.clone(),
+            };
+            let mut session = client::connect(config, (host, port), handler).await?;
+            let is_authenticated = match authentication {
+                SshAuthentication::Password(password) => {
+                    session.authenticate_password(username, password).await?
+                }
+                SshAuthentication::PublicKey(key_pair) => {
+                    session.authenticate_publickey(username, key_pair).await?
+                }
+            };
+            if !is_authenticated {
+                return Err(SshError::AuthenticationFailed);
+            }
+            let host_key_fingerprint = server_fingerprint
+                .lock()
+                .ok()
+                .and_then(|fingerprint| fingerprint.clone())
+                .unwrap_or_default();
 
             Ok(SshService {
                 client: Arc::new(session),
+                host_key_fingerprint,
             })
         };
 
@@ -60,6 +138,10 @@ impl SshService {
         }
     }
 
+    pub fn host_key_fingerprint(&self) -> &str {
+        &self.host_key_fingerprint
+    }
+
     pub async fn execute_command(
         &self,
         command: &str,
@@ -108,6 +190,55 @@ impl SshService {
         }
     }
 
+    pub async fn upload_files(
+        &self,
+        destination_folder: &str,
+        files: &[ArrayBufferWithPath],
+    ) -> Result<u64, SshError> {
+        let archive = Self::create_tar_archive(files)?;
+        let command = RemoteCommand::new("mkdir")
+            .arg("-p")
+            .arg(destination_folder)
+            .and(
+                RemoteCommand::new("tar")
+                    .args(["-xf", "-", "-C"])
+                    .arg(destination_folder),
+            );
+        self.execute_command(&command.to_string(), Some(&archive))
+            .await?;
+
+        Ok(archive.len() as u64)
+    }
+
+    fn create_tar_archive(files: &[ArrayBufferWithPath]) -> Result<Vec<u8>, SshError> {
+        let modified_time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        let mut builder = Builder::new(Vec::new());
+
+        for file in files {
+            let path = file.path.trim_start_matches("/");
+            let mut header = Header::new_gnu();
+            header.set_mode(file.mode.unwrap_or(Self::DEFAULT_FILE_MODE) & 0o7777);
+            header.set_mtime(modified_time);
+            if file.is_symlink() {
+                let target = String::from_utf8_lossy(&file.buffer);
+                header.set_entry_type(EntryType::Symlink);
+                header.set_size(0);
+                builder.append_link(&mut header, path, target.as_ref())?;
+            } else {
+                header.set_entry_type(EntryType::Regular);
+                header.set_size(file.buffer.len() as u64);
+                builder.append_data(&mut header, path, file.buffer.as_slice())?;
+            }
+        }
+
+        let archive = builder.into_inner()?;
+
+        Ok(archive)
+    }
+
     pub async fn disconnect(&self) -> Result<(), SshError> {
         self.client
             .disconnect(Disconnect::ByApplication, "", "English")
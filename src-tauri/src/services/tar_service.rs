@@ -0,0 +1,140 @@
+use super::archive_service::ArchiveService;
+use super::plugins::SYMLINK_TYPE;
+use super::utils::{create_symlink, remove_path, set_file_mode};
+use crate::mod_plugin_controller::ArrayBufferWithPath;
+use std::fs::{self, File};
+use std::io::{self, ErrorKind, Read};
+use std::path::{Path, PathBuf};
+use tar::{Archive, Entry};
+
+pub struct TarService;
+
+impl TarService {
+    pub fn list_files(reader: impl Read) -> Result<Vec<PathBuf>, io::Error> {
+        let mut archive = Archive::new(reader);
+        let mut result = Vec::new();
+
+        for entry in archive.entries()? {
+            let entry = entry?;
+            if entry.header().entry_type().is_dir() {
+                continue;
+            }
+            if let Some(path) = ArchiveService::get_enclosed_path(&entry.path()?) {
+                result.push(path);
+            }
+        }
+
+        Ok(result)
+    }
+
+    pub fn unpack_prefix(
+        reader: impl Read,
+        prefix: &Path,
+        destination: &Path,
+    ) -> Result<(), io::Error> {
+        let mut archive = Archive::new(reader);
+        let mut links = Vec::new();
+
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let path = match ArchiveService::get_enclosed_path(&entry.path()?) {
+                Some(path) => path,
+                None => continue,
+            };
+            let output_path = match ArchiveService::get_output_path(&path, prefix, destination) {
+                Some(path) => path,
+                None => continue,
+            };
+            ArchiveService::check_output_path(&output_path, destination)?;
+            let entry_type = entry.header().entry_type();
+
+            if entry_type.is_dir() {
+                fs::create_dir_all(&output_path)?;
+                continue;
+            }
+            if let Some(p) = output_path.parent() {
+                if !p.exists() {
+                    fs::create_dir_all(&p)?;
+                }
+            }
+
+            // Hard links and device files don't belong in a plugin bundle
+            if entry_type.is_symlink() {
+                let target = Self::get_link_target(&entry, &path)?;
+                ArchiveService::check_link(&path, &target, prefix)?;
+                remove_path(&output_path)?;
+                create_symlink(&target, &output_path)?;
+                links.push(output_path);
+            } else if entry_type.is_file() {
+                let mut output_file = File::create(&output_path)?;
+                io::copy(&mut entry, &mut output_file)?;
+                set_file_mode(&output_path, entry.header().mode()?)?;
+            }
+        }
+
+        ArchiveService::check_links(&links, destination)
+    }
+
+    pub fn read_files(
+        reader: impl Read,
+        starts_with: &PathBuf,
+    ) -> Result<Vec<ArrayBufferWithPath>, io::Error> {
+        let mut result: Vec<ArrayBufferWithPath> = Vec::new();
+        let mut archive = Archive::new(reader);
+        let starts_with_parent = match starts_with.parent() {
+            Some(parent) => parent,
+            None => return Ok(result),
+        };
+
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let path = match ArchiveService::get_enclosed_path(&entry.path()?) {
+                Some(path) => path,
+                None => continue,
+            };
+            if !path.starts_with(starts_with) {
+                continue;
+            };
+            let stripped_path = match path.strip_prefix(starts_with_parent) {
+                Ok(p) => p,
+                Err(_) => continue,
+            };
+            let entry_type = entry.header().entry_type();
+            let mode = entry.header().mode()?;
+
+            if entry_type.is_symlink() {
+                let target = Self::get_link_target(&entry, stripped_path)?;
+                ArchiveService::check_link(
+                    stripped_path,
+                    &target,
+                    &ArchiveService::get_root(stripped_path),
+                )?;
+                result.push(ArrayBufferWithPath {
+                    path: stripped_path.to_string_lossy().to_string(),
+                    buffer: target.to_string_lossy().as_bytes().to_vec(),
+                    mode: Some(SYMLINK_TYPE | mode & 0o7777),
+                });
+            } else if entry_type.is_file() {
+                let mut buffer = Vec::new();
+                entry.read_to_end(&mut buffer)?;
+                result.push(ArrayBufferWithPath {
+                    path: stripped_path.to_string_lossy().to_string(),
+                    buffer,
+                    mode: Some(mode),
+                });
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn get_link_target<R: Read>(entry: &Entry<R>, path: &Path) -> Result<PathBuf, io::Error> {
+        match entry.link_name()? {
+            Some(target) => Ok(target.into_owned()),
+            None => Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("{} is a symlink without a target", path.display()),
+            )),
+        }
+    }
+}
@@ -1,15 +1,39 @@
+use super::archive_service::ArchiveService;
+use super::plugins::is_symlink_mode;
+use super::utils::{create_symlink, remove_path, set_file_mode};
 use crate::mod_plugin_controller::ArrayBufferWithPath;
 use std::fs::{self, File};
 use std::io::{self, Read};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use zip::ZipArchive;
 
 pub struct ZipService;
 
 impl ZipService {
-    pub fn unzip(file_path: &PathBuf) -> Result<(), io::Error> {
+    pub fn list_files(file_path: &PathBuf) -> Result<Vec<PathBuf>, io::Error> {
         let file = File::open(file_path)?;
         let mut archive = ZipArchive::new(file)?;
+        let mut result = Vec::new();
+
+        for i in 0..archive.len() {
+            // Raw access skips decompressing the contents
+            let file = archive.by_index_raw(i)?;
+            if let (Some(path), true) = (file.enclosed_name(), file.is_file()) {
+                result.push(path);
+            }
+        }
+
+        Ok(result)
+    }
+
+    pub fn unzip_prefix(
+        file_path: &PathBuf,
+        prefix: &Path,
+        destination: &Path,
+    ) -> Result<(), io::Error> {
+        let file = File::open(file_path)?;
+        let mut archive = ZipArchive::new(file)?;
+        let mut links = Vec::new();
 
         for i in 0..archive.len() {
             let mut file = archive.by_index(i)?;
@@ -17,11 +41,11 @@ impl ZipService {
                 Some(path) => path,
                 None => continue,
             };
-            let folder = match file_path.parent() {
+            let output_path = match ArchiveService::get_output_path(&path, prefix, destination) {
                 Some(path) => path,
                 None => continue,
             };
-            let output_path = folder.join(PathBuf::from(path));
+            ArchiveService::check_output_path(&output_path, destination)?;
 
             // If the entry is a directory, create it
             if file.is_dir() {
@@ -34,13 +58,28 @@ impl ZipService {
                     }
                 }
 
+                let mode = file.unix_mode();
+                if is_symlink_mode(mode) {
+                    let mut target = String::new();
+                    file.read_to_string(&mut target)?;
+                    let target = PathBuf::from(target);
+                    ArchiveService::check_link(&path, &target, prefix)?;
+                    remove_path(&output_path)?;
+                    create_symlink(&target, &output_path)?;
+                    links.push(output_path);
+                    continue;
+                }
+
                 // Extract the file
                 let mut output_file = File::create(&output_path)?;
                 io::copy(&mut file, &mut output_file)?;
+                if let Some(mode) = mode {
+                    set_file_mode(&output_path, mode)?;
+                }
             }
         }
 
-        Ok(())
+        ArchiveService::check_links(&links, destination)
     }
 
     pub fn unzip_to_u8(
@@ -71,9 +110,18 @@ impl ZipService {
             let path = stripped_path.to_string_lossy().to_string();
 
             if file.is_file() {
+                let mode = file.unix_mode();
                 let mut buffer = Vec::new();
                 file.read_to_end(&mut buffer)?;
-                result.push(ArrayBufferWithPath { path, buffer })
+                if is_symlink_mode(mode) {
+                    let target = PathBuf::from(String::from_utf8_lossy(&buffer).as_ref());
+                    ArchiveService::check_link(
+                        stripped_path,
+                        &target,
+                        &ArchiveService::get_root(stripped_path),
+                    )?;
+                }
+                result.push(ArrayBufferWithPath { path, buffer, mode })
             }
         }
 
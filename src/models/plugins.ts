@@ -6,7 +6,55 @@ export type PluginFolders = {
   clapFolder?: string;
 };
 
+export type PluginVersion = {
+  version: string;
+  tag: string | null;
+  installedAt: number | null;
+};
+export type PluginEntry = {
+  name: string;
+  displayName: string | null;
+  description: string | null;
+  category: string | null;
+  repo: string | null;
+  minAppVersion: string | null;
+  platforms: string[] | null;
+  archiveFormat: "zip" | "tar.gz" | "tar.xz" | null;
+  architectures: string[] | null;
+  signed: boolean;
+};
 export type FetchPluginsResponse = Record<PluginFormat, string[]> & {
     modIsConnected?: boolean;
+    installedVersions?: Record<PluginFormat, Record<string, PluginVersion>>;
+    pluginInfo?: Record<string, PluginEntry>;
 }
-export type SelectedPlugins = Record<PluginFormat, string[]>
\ No newline at end of file
+export type PinnedPlugin = { name: string; tag?: string };
+export type SelectedPlugins = Record<PluginFormat, (string | PinnedPlugin)[]>
+export type PluginProgress = {
+  plugin: string;
+  format: PluginFormat;
+  phase: "downloading" | "extracting" | "copying" | "uploading" | "done";
+  bytesDone: number;
+  bytesTotal: number | null;
+};
+export type PluginUpdate = {
+  name: string;
+  format: PluginFormat;
+  installedVersion: string | null;
+  latestVersion: string | null;
+  latestTag: string | null;
+  status: "upToDate" | "updateAvailable" | "unknown";
+};
+export type PluginRelease = {
+  tag: string;
+  version: string;
+  name: string | null;
+  publishedAt: string | null;
+  prerelease: boolean;
+};
+export type CacheInfo = {
+  path: string;
+  entries: number;
+  size: number;
+  maxSize: number;
+};
@@ -8,7 +8,11 @@ import { error } from "@tauri-apps/plugin-log";
 import { Skeleton } from "./PluginManagerPage2/Skeleton";
 import { usePluginContext } from "@/hooks/usePluginContext";
 import { useNavigate } from "react-router-dom";
-import { FetchPluginsResponse, SelectedPlugins } from "@/models/plugins";
+import {
+  FetchPluginsResponse,
+  PinnedPlugin,
+  SelectedPlugins,
+} from "@/models/plugins";
 
 const initialPlugins: FetchPluginsResponse = {
   VST3: [],
@@ -17,6 +21,12 @@ const initialPlugins: FetchPluginsResponse = {
   modIsConnected: undefined,
 };
 
+function getPluginNames(plugins: (string | PinnedPlugin)[]) {
+  return plugins.map((plugin) =>
+    typeof plugin === "string" ? plugin : plugin.name,
+  );
+}
+
 export function PluginManagerPage2() {
   const { mode, selectedPluginFormats, selectedModPlatform, pluginFolders } =
     usePluginContext();
@@ -114,7 +124,7 @@ export function PluginManagerPage2() {
           <CheckboxList
             title="VST3"
             items={plugins.VST3}
-            selectedItems={selectedPlugins.VST3}
+            selectedItems={getPluginNames(selectedPlugins.VST3)}
             disabled={isProcessing || !plugins.VST3.length}
             onChange={(items) => {
               setSelectedPlugins({ ...selectedPlugins, VST3: items });
@@ -135,7 +145,7 @@ export function PluginManagerPage2() {
           <CheckboxList
             title="CLAP"
             items={plugins.CLAP}
-            selectedItems={selectedPlugins.CLAP}
+            selectedItems={getPluginNames(selectedPlugins.CLAP)}
             disabled={isProcessing || !plugins.CLAP.length}
             onChange={(items) => {
               setSelectedPlugins({ ...selectedPlugins, CLAP: items });
@@ -156,7 +166,7 @@ export function PluginManagerPage2() {
           <CheckboxList
             title={`MOD ${selectedModPlatform}`}
             items={plugins.modIsConnected ? plugins["MOD Audio"] : []}
-            selectedItems={selectedPlugins["MOD Audio"]}
+            selectedItems={getPluginNames(selectedPlugins["MOD Audio"])}
             disabled={
               isProcessing ||
               !plugins.modIsConnected ||